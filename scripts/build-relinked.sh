@@ -5,22 +5,35 @@ set -eux
 export CARGO_UNSTABLE_SPARSE_REGISTRY=true
 export PATH=${PATH}:${NDK_HOME}/toolchains/llvm/prebuilt/linux-x86_64/bin
 OBJDUMP=${NDK_HOME}/toolchains/llvm/prebuilt/linux-x86_64/bin/llvm-objdump
+# Features of the relinked libraries, e.g. "table-baseline" for the benchmarks.
+FEATURES=${RELINKED_FEATURES:-}
 
 cp -rf src/relinked build/
 cd build/relinked
 
 cargo fmt --check
 
-cargo +${RUST_TOOLCHAIN} build --target aarch64-linux-android --release
+cargo +${RUST_TOOLCHAIN} build --target aarch64-linux-android --release --features "${FEATURES}"
 ${OBJDUMP} target/aarch64-linux-android/release/librelinked.a --syms | grep aes
 ${OBJDUMP} target/aarch64-linux-android/release/librelinked.a --syms | grep gf
 #${OBJDUMP} target/aarch64-linux-android/release/librelinked.a --disassemble-symbols=aesenc_fallback 2> /dev/null
 cp target/aarch64-linux-android/release/librelinked.a libfallback.a
 
-RUSTFLAGS='-C target-feature=+aes' cargo +${RUST_TOOLCHAIN} build --target aarch64-linux-android --release
+RUSTFLAGS='-C target-feature=+aes' cargo +${RUST_TOOLCHAIN} build --target aarch64-linux-android --release --features "${FEATURES}"
 ${OBJDUMP} target/aarch64-linux-android/release/librelinked.a --syms | grep aes
 ${OBJDUMP} target/aarch64-linux-android/release/librelinked.a --syms | grep gf
 #${OBJDUMP} target/aarch64-linux-android/release/librelinked.a --disassemble-symbols=aesenc_simd 2> /dev/null
 cp target/aarch64-linux-android/release/librelinked.a libsimd.a
 
+# The x86_64 libraries go in their own directory, as they have the same names.
+mkdir -p x86_64
+
+cargo +${RUST_TOOLCHAIN} build --target x86_64-linux-android --release --features "${FEATURES}"
+${OBJDUMP} target/x86_64-linux-android/release/librelinked.a --syms | grep aes
+cp target/x86_64-linux-android/release/librelinked.a x86_64/libfallback.a
+
+RUSTFLAGS='-C target-feature=+aes,+pclmulqdq' cargo +${RUST_TOOLCHAIN} build --target x86_64-linux-android --release --features "${FEATURES}"
+${OBJDUMP} target/x86_64-linux-android/release/librelinked.a --syms | grep aes
+cp target/x86_64-linux-android/release/librelinked.a x86_64/libsimd.a
+
 rm -R target
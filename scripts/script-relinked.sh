@@ -15,7 +15,7 @@ export PATH=${PATH}:${NDK_HOME}/toolchains/llvm/prebuilt/linux-x86_64/bin
 RUSTFLAGS='-L /home/dev/build/relinked' cargo +nightly build --features relink --target aarch64-linux-android --release
 cargo +nightly build --target armv7-linux-androideabi --release
 cargo +nightly build --target i686-linux-android --release
-cargo +nightly build --target x86_64-linux-android --release
+RUSTFLAGS='-L /home/dev/build/relinked/x86_64' cargo +nightly build --features relink --target x86_64-linux-android --release
 
 cd ../..
 
@@ -33,4 +33,4 @@ set +x
 echo "Cleaning up some space..."
 set -x
 
-rm build/relinked/*.a
+rm build/relinked/*.a build/relinked/x86_64/*.a
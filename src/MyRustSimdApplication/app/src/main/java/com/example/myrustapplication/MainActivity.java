@@ -6,6 +6,7 @@ import android.util.Log;
 import android.widget.TextView;
 import androidx.appcompat.app.AppCompatActivity;
 
+import java.io.File;
 import java.util.Arrays;
 
 public class MainActivity extends AppCompatActivity {
@@ -26,6 +27,13 @@ public class MainActivity extends AppCompatActivity {
         Log.i(TAG, "OS.ARCH: " + System.getProperty("os.arch"));
 
         NativeLibrary lib = new NativeLibrary();
+        lib.initLogging("info,simd=debug", new File(getFilesDir(), "logs").getPath());
+        lib.redirectStdio();
+        String previousCrash =
+                lib.installPanicHook(new File(getFilesDir(), "rust-crash.txt").getPath());
+        if (previousCrash != null) {
+            Log.e(TAG, "The native library crashed during the previous run:\n" + previousCrash);
+        }
         String message = lib.run();
         ((TextView) findViewById(R.id.greetingField)).setText(message);
     }
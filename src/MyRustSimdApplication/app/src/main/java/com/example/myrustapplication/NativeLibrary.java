@@ -6,9 +6,29 @@ public class NativeLibrary {
         System.loadLibrary("simd");
     }
 
+    /** Logs to logcat and to rotating files in the given directory. */
+    public void initLogging(String filters, String logDir) {
+        nativeInitLogging(filters, logDir);
+    }
+
+    /** Returns the crash record of the previous run, or null if there was none. */
+    public String installPanicHook(String crashRecordPath) {
+        return nativeInstallPanicHook(crashRecordPath);
+    }
+
+    public void redirectStdio() {
+        nativeRedirectStdio();
+    }
+
     public String run() {
         return nativeRun();
     }
 
+    private static native void nativeInitLogging(String filters, String logDir);
+
+    private static native String nativeInstallPanicHook(String crashRecordPath);
+
+    private static native void nativeRedirectStdio();
+
     private static native String nativeRun();
 }
@@ -0,0 +1,748 @@
+//! AES-GCM (NIST SP 800-38D) and AES-GCM-SIV (RFC 8452) authenticated encryption, combining the
+//! AES block cipher of the relinked library with the GHASH and POLYVAL universal hashes.
+//!
+//! Both take 96-bit nonces and produce 128-bit tags. GCM-SIV derives per-nonce keys and computes
+//! the tag over the plaintext, from which it derives the initial counter: reusing a nonce then only
+//! reveals whether two messages are equal, instead of the XOR of their plaintexts.
+
+use crate::aes::{self, Aes, AesStrategy, BLOCK_LEN};
+use crate::pmul::{self, PmulStrategy};
+use crate::polyval::{Ghash, Polyval};
+use std::fmt;
+use std::hint::black_box;
+
+/// Size of a nonce, in bytes.
+pub const NONCE_LEN: usize = 12;
+
+/// Size of a tag, in bytes.
+pub const TAG_LEN: usize = 16;
+
+/// Maximal length of an AES-GCM message, in bytes: 2^39 - 256 bits (SP 800-38D, 5.2.1.1).
+const GCM_MAX_LEN: u64 = (1 << 36) - 32;
+
+/// Maximal length of the additional data of AES-GCM, in bytes: 2^64 - 1 bits (SP 800-38D,
+/// 5.2.1.1).
+const GCM_MAX_AAD_LEN: u64 = (1 << 61) - 1;
+
+/// Maximal length of an AES-GCM-SIV plaintext, and of its additional data, in bytes (RFC 8452,
+/// section 6). Beyond it, the 32-bit block counter would wrap and repeat the keystream.
+const SIV_MAX_LEN: u64 = 1 << 36;
+
+/// Error returned when a strategy isn't supported by the current CPU.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Unsupported {
+    Aes(AesStrategy),
+    Pmul(PmulStrategy),
+}
+
+impl From<aes::Unsupported> for Unsupported {
+    fn from(e: aes::Unsupported) -> Self {
+        Unsupported::Aes(e.0)
+    }
+}
+
+impl From<pmul::Unsupported> for Unsupported {
+    fn from(e: pmul::Unsupported) -> Self {
+        Unsupported::Pmul(e.0)
+    }
+}
+
+/// Error returned when the tag doesn't authenticate the ciphertext and additional data.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidTag;
+
+/// Error returned when a plaintext or its additional data is too long for the mode.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TooLong;
+
+/// AES-GCM with a 128-, 192- or 256-bit key.
+#[derive(Clone)]
+pub struct AesGcm {
+    aes: Aes,
+    /// GHASH keyed with the encryption of the zero block, before absorbing any data.
+    ghash: Ghash,
+}
+
+impl AesGcm {
+    /// Creates a cipher with the given key, and the strategies selected by
+    /// `aes::preferred_strategy()` and `pmul::preferred_strategy()`.
+    ///
+    /// Panics if the key isn't 16, 24 or 32 bytes long.
+    pub fn new(key: &[u8]) -> Self {
+        Self::new_with(aes::preferred_strategy(), pmul::preferred_strategy(), key)
+            .expect("Preferred strategy is unsupported")
+    }
+
+    /// Creates a cipher with the given key and strategies, if the current CPU supports them.
+    ///
+    /// Panics if the key isn't 16, 24 or 32 bytes long.
+    pub fn new_with(
+        aes_strategy: AesStrategy,
+        pmul_strategy: PmulStrategy,
+        key: &[u8],
+    ) -> Result<Self, Unsupported> {
+        let aes = Aes::new_with(aes_strategy, key)?;
+        let mut h = [0; BLOCK_LEN];
+        aes.encrypt_block(&mut h);
+        let ghash = Ghash::new_with(pmul_strategy, &h)?;
+        Ok(AesGcm { aes, ghash })
+    }
+
+    /// Encrypts the plaintext and returns the ciphertext followed by the tag.
+    ///
+    /// Fails if the plaintext is longer than 2^36 - 32 bytes.
+    pub fn seal(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, TooLong> {
+        let mut result = Vec::with_capacity(plaintext.len() + TAG_LEN);
+        result.extend_from_slice(plaintext);
+        let tag = self.seal_in_place(nonce, aad, &mut result)?;
+        result.extend_from_slice(&tag);
+        Ok(result)
+    }
+
+    /// Authenticates and decrypts a ciphertext followed by its tag.
+    pub fn open(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, InvalidTag> {
+        let (ciphertext, tag) = split_tag(ciphertext)?;
+        let mut result = ciphertext.to_vec();
+        self.open_in_place(nonce, aad, &mut result, tag)?;
+        Ok(result)
+    }
+
+    /// Encrypts the buffer in place, and returns the tag.
+    ///
+    /// Fails if the buffer is longer than 2^36 - 32 bytes.
+    pub fn seal_in_place(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<[u8; TAG_LEN], TooLong> {
+        gcm_check_lengths(aad.len() as u64, buffer.len() as u64)?;
+        let j0 = gcm_initial_counter(nonce);
+        self.apply_keystream(&j0, buffer);
+        Ok(self.tag(&j0, aad, buffer))
+    }
+
+    /// Authenticates the buffer and decrypts it in place. The buffer is left unchanged if the tag
+    /// is invalid.
+    ///
+    /// Lengths that `seal_in_place()` rejects are reported as an invalid tag, as no valid
+    /// ciphertext can have them.
+    pub fn open_in_place(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &[u8; TAG_LEN],
+    ) -> Result<(), InvalidTag> {
+        gcm_check_lengths(aad.len() as u64, buffer.len() as u64).map_err(|TooLong| InvalidTag)?;
+        let j0 = gcm_initial_counter(nonce);
+        if !tags_equal(&self.tag(&j0, aad, buffer), tag) {
+            return Err(InvalidTag);
+        }
+        self.apply_keystream(&j0, buffer);
+        Ok(())
+    }
+
+    /// Encrypts or decrypts the buffer in counter mode, from the block after `j0`.
+    ///
+    /// GCM only increments the last 32 bits of the counter, which start at 2 and can't wrap within
+    /// the maximal length of a message, so the 128-bit counter of `Aes::apply_ctr()` is equivalent.
+    fn apply_keystream(&self, j0: &[u8; BLOCK_LEN], buffer: &mut [u8]) {
+        let mut counter = *j0;
+        gcm_increment(&mut counter);
+        self.aes.apply_ctr(&mut counter, buffer);
+    }
+
+    fn tag(&self, j0: &[u8; BLOCK_LEN], aad: &[u8], ciphertext: &[u8]) -> [u8; TAG_LEN] {
+        let mut ghash = self.ghash.clone();
+        ghash.update_padded(aad);
+        ghash.update_padded(ciphertext);
+        let mut lengths = [0; BLOCK_LEN];
+        lengths[..8].copy_from_slice(&(aad.len() as u64 * 8).to_be_bytes());
+        lengths[8..].copy_from_slice(&(ciphertext.len() as u64 * 8).to_be_bytes());
+        ghash.update_padded(&lengths);
+
+        let mut tag = *j0;
+        self.aes.encrypt_block(&mut tag);
+        xor_block(&mut tag, &ghash.finalize());
+        tag
+    }
+}
+
+// The keys are secret, so they must not end up in the logs.
+impl fmt::Debug for AesGcm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AesGcm")
+            .field("aes_strategy", &self.aes.strategy())
+            .field("pmul_strategy", &self.ghash.strategy())
+            .field("key_len", &self.aes.key_len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// AES-GCM-SIV with a 128- or 256-bit key.
+#[derive(Clone)]
+pub struct AesGcmSiv {
+    key_generating_key: Aes,
+    key_len: usize,
+    pmul_strategy: PmulStrategy,
+}
+
+impl AesGcmSiv {
+    /// Creates a cipher with the given key, and the strategies selected by
+    /// `aes::preferred_strategy()` and `pmul::preferred_strategy()`.
+    ///
+    /// Panics if the key isn't 16 or 32 bytes long.
+    pub fn new(key: &[u8]) -> Self {
+        Self::new_with(aes::preferred_strategy(), pmul::preferred_strategy(), key)
+            .expect("Preferred strategy is unsupported")
+    }
+
+    /// Creates a cipher with the given key and strategies, if the current CPU supports them.
+    ///
+    /// Panics if the key isn't 16 or 32 bytes long.
+    pub fn new_with(
+        aes_strategy: AesStrategy,
+        pmul_strategy: PmulStrategy,
+        key: &[u8],
+    ) -> Result<Self, Unsupported> {
+        assert!(
+            matches!(key.len(), 16 | 32),
+            "Invalid AES-GCM-SIV key length: {}",
+            key.len()
+        );
+        if !pmul_strategy.is_available() {
+            return Err(Unsupported::Pmul(pmul_strategy));
+        }
+        Ok(AesGcmSiv {
+            key_generating_key: Aes::new_with(aes_strategy, key)?,
+            key_len: key.len(),
+            pmul_strategy,
+        })
+    }
+
+    /// Encrypts the plaintext and returns the ciphertext followed by the tag.
+    ///
+    /// Fails if the plaintext or the additional data is longer than 2^36 bytes.
+    pub fn seal(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, TooLong> {
+        let mut result = Vec::with_capacity(plaintext.len() + TAG_LEN);
+        result.extend_from_slice(plaintext);
+        let tag = self.seal_in_place(nonce, aad, &mut result)?;
+        result.extend_from_slice(&tag);
+        Ok(result)
+    }
+
+    /// Authenticates and decrypts a ciphertext followed by its tag.
+    pub fn open(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, InvalidTag> {
+        let (ciphertext, tag) = split_tag(ciphertext)?;
+        let mut result = ciphertext.to_vec();
+        self.open_in_place(nonce, aad, &mut result, tag)?;
+        Ok(result)
+    }
+
+    /// Encrypts the buffer in place, and returns the tag.
+    ///
+    /// Fails if the buffer or the additional data is longer than 2^36 bytes.
+    pub fn seal_in_place(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<[u8; TAG_LEN], TooLong> {
+        siv_check_lengths(aad.len() as u64, buffer.len() as u64)?;
+        let (polyval, aes) = self.derive_keys(nonce);
+        let tag = Self::tag(polyval, &aes, nonce, aad, buffer);
+        siv_apply_keystream(&aes, &tag, buffer);
+        Ok(tag)
+    }
+
+    /// Decrypts the buffer in place and authenticates the plaintext. The buffer is left unchanged
+    /// if the tag is invalid.
+    ///
+    /// Lengths that `seal_in_place()` rejects are reported as an invalid tag, as RFC 8452 fails
+    /// the same way for both.
+    pub fn open_in_place(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &[u8; TAG_LEN],
+    ) -> Result<(), InvalidTag> {
+        siv_check_lengths(aad.len() as u64, buffer.len() as u64).map_err(|TooLong| InvalidTag)?;
+        let (polyval, aes) = self.derive_keys(nonce);
+        siv_apply_keystream(&aes, tag, buffer);
+        if !tags_equal(&Self::tag(polyval, &aes, nonce, aad, buffer), tag) {
+            // Restores the ciphertext, so that no unauthenticated plaintext is released.
+            siv_apply_keystream(&aes, tag, buffer);
+            return Err(InvalidTag);
+        }
+        Ok(())
+    }
+
+    /// Derives the message-authentication and message-encryption keys for the nonce, from the
+    /// first halves of the encrypted blocks `le32(i) || nonce` (RFC 8452, section 4).
+    fn derive_keys(&self, nonce: &[u8; NONCE_LEN]) -> (Polyval, Aes) {
+        let mut keys = [0; BLOCK_LEN + 32];
+        for (i, half) in keys[..BLOCK_LEN + self.key_len]
+            .chunks_mut(BLOCK_LEN / 2)
+            .enumerate()
+        {
+            let mut block = [0; BLOCK_LEN];
+            block[..4].copy_from_slice(&(i as u32).to_le_bytes());
+            block[4..].copy_from_slice(nonce);
+            self.key_generating_key.encrypt_block(&mut block);
+            half.copy_from_slice(&block[..BLOCK_LEN / 2]);
+        }
+        let (auth_key, enc_key) = keys[..BLOCK_LEN + self.key_len].split_at(BLOCK_LEN);
+        let polyval = Polyval::new_with(self.pmul_strategy, auth_key.try_into().unwrap())
+            .expect("Strategy is unsupported");
+        let aes = Aes::new_with(self.key_generating_key.strategy(), enc_key)
+            .expect("Strategy is unsupported");
+        (polyval, aes)
+    }
+
+    fn tag(
+        mut polyval: Polyval,
+        aes: &Aes,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> [u8; TAG_LEN] {
+        polyval.update_padded(aad);
+        polyval.update_padded(plaintext);
+        let mut lengths = [0; BLOCK_LEN];
+        lengths[..8].copy_from_slice(&(aad.len() as u64 * 8).to_le_bytes());
+        lengths[8..].copy_from_slice(&(plaintext.len() as u64 * 8).to_le_bytes());
+        polyval.update_padded(&lengths);
+
+        let mut tag = polyval.finalize();
+        for (x, n) in tag.iter_mut().zip(nonce) {
+            *x ^= n;
+        }
+        tag[15] &= 0x7f;
+        aes.encrypt_block(&mut tag);
+        tag
+    }
+}
+
+impl fmt::Debug for AesGcmSiv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AesGcmSiv")
+            .field("aes_strategy", &self.key_generating_key.strategy())
+            .field("pmul_strategy", &self.pmul_strategy)
+            .field("key_len", &self.key_len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Splits the tag at the end of a ciphertext.
+fn split_tag(ciphertext: &[u8]) -> Result<(&[u8], &[u8; TAG_LEN]), InvalidTag> {
+    if ciphertext.len() < TAG_LEN {
+        return Err(InvalidTag);
+    }
+    let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+    Ok((ciphertext, tag.try_into().unwrap()))
+}
+
+/// Counter block J0 of GCM for a 96-bit nonce, used to encrypt the tag.
+fn gcm_initial_counter(nonce: &[u8; NONCE_LEN]) -> [u8; BLOCK_LEN] {
+    let mut counter = [0; BLOCK_LEN];
+    counter[..NONCE_LEN].copy_from_slice(nonce);
+    counter[15] = 1;
+    counter
+}
+
+/// Increments the last 32 bits of the counter block as a big-endian integer, i.e. inc32() of GCM.
+fn gcm_increment(counter: &mut [u8; BLOCK_LEN]) {
+    let low = u32::from_be_bytes(counter[12..].try_into().unwrap());
+    counter[12..].copy_from_slice(&low.wrapping_add(1).to_be_bytes());
+}
+
+/// Checks the lengths of an AES-GCM message and of its additional data.
+fn gcm_check_lengths(aad_len: u64, len: u64) -> Result<(), TooLong> {
+    if aad_len > GCM_MAX_AAD_LEN || len > GCM_MAX_LEN {
+        return Err(TooLong);
+    }
+    Ok(())
+}
+
+/// Checks the lengths of an AES-GCM-SIV message and of its additional data.
+fn siv_check_lengths(aad_len: u64, len: u64) -> Result<(), TooLong> {
+    if aad_len > SIV_MAX_LEN || len > SIV_MAX_LEN {
+        return Err(TooLong);
+    }
+    Ok(())
+}
+
+/// Encrypts or decrypts the buffer in the counter mode of GCM-SIV, whose initial counter block is
+/// the tag with its most significant bit set, and whose first 32 bits are incremented as a
+/// little-endian integer.
+fn siv_apply_keystream(aes: &Aes, tag: &[u8; TAG_LEN], buffer: &mut [u8]) {
+    let mut counter = *tag;
+    counter[15] |= 0x80;
+    aes.apply_ctr32_le(&mut counter, buffer);
+}
+
+fn xor_block(a: &mut [u8; BLOCK_LEN], b: &[u8; BLOCK_LEN]) {
+    for (x, y) in a.iter_mut().zip(b) {
+        *x ^= y;
+    }
+}
+
+/// Compares the tags in constant time: the differences of all the bytes are accumulated, hiding
+/// their values from the optimizer so that it can't exit early.
+fn tags_equal(a: &[u8; TAG_LEN], b: &[u8; TAG_LEN]) -> bool {
+    a.iter()
+        .zip(b)
+        .fold(0, |diff, (x, y)| black_box(diff | (x ^ y)))
+        == 0
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::pmul::available_strategies;
+    use crate::testutil::hex_bytes;
+    use test::Bencher;
+
+    /// Key, nonce, additional data, plaintext, and ciphertext followed by the tag.
+    type Vector = (
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    );
+
+    const GCM_PLAINTEXT: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+        1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+
+    /// Test cases 1, 2, 4, 10 and 16 of the GCM specification (McGrew and Viega).
+    const GCM_VECTORS: [Vector; 5] = [
+        (
+            "00000000000000000000000000000000",
+            "000000000000000000000000",
+            "",
+            "",
+            "58e2fccefa7e3061367f1d57a4e7455a",
+        ),
+        (
+            "00000000000000000000000000000000",
+            "000000000000000000000000",
+            "",
+            "00000000000000000000000000000000",
+            "0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf",
+        ),
+        (
+            "feffe9928665731c6d6a8f9467308308",
+            "cafebabefacedbaddecaf888",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            GCM_PLAINTEXT,
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+            21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091\
+            5bc94fbc3221a5db94fae95ae7121a47",
+        ),
+        (
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c",
+            "cafebabefacedbaddecaf888",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            GCM_PLAINTEXT,
+            "3980ca0b3c00e841eb06fac4872a2757859e1ceaa6efd984628593b40ca1e19c\
+            7d773d00c144c525ac619d18c84a3f4718e2448b2fe324d9ccda2710\
+            2519498e80f1478f37ba55bd6d27618c",
+        ),
+        (
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+            "cafebabefacedbaddecaf888",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            GCM_PLAINTEXT,
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+            8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662\
+            76fc6ece0f4e1768cddf8853bb2d551b",
+        ),
+    ];
+
+    /// Test vectors of RFC 8452, Appendix C.1 and C.2.
+    const SIV_VECTORS: [Vector; 5] = [
+        (
+            "01000000000000000000000000000000",
+            "030000000000000000000000",
+            "",
+            "",
+            "dc20e2d83f25705bb49e439eca56de25",
+        ),
+        (
+            "01000000000000000000000000000000",
+            "030000000000000000000000",
+            "",
+            "0100000000000000",
+            "b5d839330ac7b786578782fff6013b815b287c22493a364c",
+        ),
+        (
+            "ee8e1ed9ff2540ae8f2ba9f50bc2f27c",
+            "752abad3e0afb5f434dc4310",
+            "6578616d706c65",
+            "48656c6c6f20776f726c64",
+            "5d349ead175ef6b1def6fd4fbcdeb7e4793f4a1d7e4faa70100af1",
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "030000000000000000000000",
+            "",
+            "",
+            "07f5f4169bbf55a8400cd47ea6fd400f",
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "030000000000000000000000",
+            "",
+            "0100000000000000",
+            "c2ef328e5c71c83b843122130f7364b761e0b97427e3df28",
+        ),
+    ];
+
+    /// Calls the function with all the pairs of strategies supported by the current CPU.
+    fn for_each_strategy(mut f: impl FnMut(AesStrategy, PmulStrategy)) {
+        for aes_strategy in AesStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+            for pmul_strategy in available_strategies() {
+                f(aes_strategy, pmul_strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gcm_vectors() {
+        for_each_strategy(|aes_strategy, pmul_strategy| {
+            for (key, nonce, aad, plaintext, expected) in GCM_VECTORS {
+                let gcm = AesGcm::new_with(aes_strategy, pmul_strategy, &hex_bytes(key)).unwrap();
+                let nonce = hex_bytes(nonce).try_into().unwrap();
+                let (aad, plaintext) = (hex_bytes(aad), hex_bytes(plaintext));
+                let ciphertext = gcm.seal(&nonce, &aad, &plaintext).unwrap();
+                assert_eq!(ciphertext, hex_bytes(expected));
+                assert_eq!(gcm.open(&nonce, &aad, &ciphertext), Ok(plaintext));
+            }
+        });
+    }
+
+    #[test]
+    fn test_siv_vectors() {
+        for_each_strategy(|aes_strategy, pmul_strategy| {
+            for (key, nonce, aad, plaintext, expected) in SIV_VECTORS {
+                let siv =
+                    AesGcmSiv::new_with(aes_strategy, pmul_strategy, &hex_bytes(key)).unwrap();
+                let nonce = hex_bytes(nonce).try_into().unwrap();
+                let (aad, plaintext) = (hex_bytes(aad), hex_bytes(plaintext));
+                let ciphertext = siv.seal(&nonce, &aad, &plaintext).unwrap();
+                assert_eq!(ciphertext, hex_bytes(expected));
+                assert_eq!(siv.open(&nonce, &aad, &ciphertext), Ok(plaintext));
+            }
+        });
+    }
+
+    #[test]
+    fn test_long_messages() {
+        // Computed with the AESGCM and AESGCMSIV classes of the Python cryptography package.
+        let key = [1; 32];
+        let nonce = hex_bytes("030405060708090a0b0c0d0e").try_into().unwrap();
+        let plaintext = (0..100).collect::<Vec<u8>>();
+        let aad = (200..255).collect::<Vec<u8>>();
+        assert_eq!(
+            AesGcm::new(&key).seal(&nonce, &aad, &plaintext),
+            Ok(hex_bytes(
+                "f63b1fddb4eae675047b6e5d392a4ea267b59e8f3356e3edb1235ae79012f71d\
+                9ceaaf3b120954b3135510dfa58eef81242db8cab2ebbc833dc10470b4a67ec0\
+                4839b00918762fd856ea3e6e63aebd35d89f18aae7056385ce7318b0710fec4d\
+                65169c9a599a514d96ea1982505d1fb80a1abeea"
+            ))
+        );
+        assert_eq!(
+            AesGcmSiv::new(&key).seal(&nonce, &aad, &plaintext),
+            Ok(hex_bytes(
+                "d2c9f07fdbbf6bd969f730b43af9ae681b5008595625a52638c49e18a591a40f\
+                75548b5c886122737672fc0d4b3cc9f4987bd2ad2f9f7566a4062ed8253b5898\
+                dc6f6fd219a572196f6eed548a78f6f626418db176dc87c72743b3b75df38136\
+                681d543dd056d1d97a6b9978a82e3d1301e66f81"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_in_place() {
+        let nonce = [7; NONCE_LEN];
+        let plaintext = (0..50).collect::<Vec<u8>>();
+
+        let gcm = AesGcm::new(&[2; 16]);
+        let mut buffer = plaintext.clone();
+        let tag = gcm.seal_in_place(&nonce, b"aad", &mut buffer).unwrap();
+        assert_eq!(
+            Ok([&buffer[..], &tag].concat()),
+            gcm.seal(&nonce, b"aad", &plaintext)
+        );
+        assert_eq!(gcm.open_in_place(&nonce, b"aad", &mut buffer, &tag), Ok(()));
+        assert_eq!(buffer, plaintext);
+
+        let siv = AesGcmSiv::new(&[2; 16]);
+        let mut buffer = plaintext.clone();
+        let tag = siv.seal_in_place(&nonce, b"aad", &mut buffer).unwrap();
+        assert_eq!(
+            Ok([&buffer[..], &tag].concat()),
+            siv.seal(&nonce, b"aad", &plaintext)
+        );
+        assert_eq!(siv.open_in_place(&nonce, b"aad", &mut buffer, &tag), Ok(()));
+        assert_eq!(buffer, plaintext);
+    }
+
+    /// Checks that `open` rejects the ciphertext of "plaintext", sealed with the nonce [7; 12] and
+    /// the additional data "aad", once modified in any way.
+    fn check_invalid_tags(
+        ciphertext: &[u8],
+        open: impl Fn(&[u8; NONCE_LEN], &[u8], &[u8]) -> Result<Vec<u8>, InvalidTag>,
+    ) {
+        let nonce = [7; NONCE_LEN];
+        assert_eq!(open(&nonce, b"aad", ciphertext), Ok(b"plaintext".to_vec()));
+        assert_eq!(open(&[8; NONCE_LEN], b"aad", ciphertext), Err(InvalidTag));
+        assert_eq!(open(&nonce, b"aae", ciphertext), Err(InvalidTag));
+        assert_eq!(open(&nonce, b"aad", &ciphertext[1..]), Err(InvalidTag));
+        assert_eq!(
+            open(&nonce, b"aad", &ciphertext[..TAG_LEN - 1]),
+            Err(InvalidTag)
+        );
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.to_vec();
+            tampered[i] ^= 0x10;
+            assert_eq!(open(&nonce, b"aad", &tampered), Err(InvalidTag));
+        }
+    }
+
+    #[test]
+    fn test_invalid_tag() {
+        let nonce = [7; NONCE_LEN];
+        let gcm = AesGcm::new(&[2; 16]);
+        check_invalid_tags(
+            &gcm.seal(&nonce, b"aad", b"plaintext").unwrap(),
+            |nonce, aad, ciphertext| gcm.open(nonce, aad, ciphertext),
+        );
+        let siv = AesGcmSiv::new(&[2; 16]);
+        check_invalid_tags(
+            &siv.seal(&nonce, b"aad", b"plaintext").unwrap(),
+            |nonce, aad, ciphertext| siv.open(nonce, aad, ciphertext),
+        );
+    }
+
+    #[test]
+    fn test_open_in_place_invalid_tag() {
+        let nonce = [7; NONCE_LEN];
+        let gcm = AesGcm::new(&[2; 16]);
+        let siv = AesGcmSiv::new(&[2; 16]);
+
+        let mut buffer = b"plaintext".to_vec();
+        let mut tag = gcm.seal_in_place(&nonce, b"", &mut buffer).unwrap();
+        tag[0] ^= 1;
+        let ciphertext = buffer.clone();
+        assert_eq!(
+            gcm.open_in_place(&nonce, b"", &mut buffer, &tag),
+            Err(InvalidTag)
+        );
+        assert_eq!(buffer, ciphertext);
+
+        let mut buffer = b"plaintext".to_vec();
+        let mut tag = siv.seal_in_place(&nonce, b"", &mut buffer).unwrap();
+        tag[0] ^= 1;
+        let ciphertext = buffer.clone();
+        assert_eq!(
+            siv.open_in_place(&nonce, b"", &mut buffer, &tag),
+            Err(InvalidTag)
+        );
+        assert_eq!(buffer, ciphertext);
+    }
+
+    #[test]
+    fn test_counter_wrap() {
+        let mut counter = [0xff; BLOCK_LEN];
+        gcm_increment(&mut counter);
+        assert_eq!(counter[..12], [0xff; 12]);
+        assert_eq!(counter[12..], [0; 4]);
+    }
+
+    #[test]
+    fn test_gcm_max_lengths() {
+        assert_eq!(gcm_check_lengths((1 << 61) - 1, (1 << 36) - 32), Ok(()));
+        assert_eq!(gcm_check_lengths(1 << 61, 0), Err(TooLong));
+        assert_eq!(gcm_check_lengths(0, (1 << 36) - 31), Err(TooLong));
+    }
+
+    #[test]
+    fn test_siv_max_lengths() {
+        assert_eq!(siv_check_lengths(1 << 36, 1 << 36), Ok(()));
+        assert_eq!(siv_check_lengths((1 << 36) + 1, 0), Err(TooLong));
+        assert_eq!(siv_check_lengths(0, (1 << 36) + 1), Err(TooLong));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid AES-GCM-SIV key length: 24")]
+    fn test_siv_invalid_key() {
+        AesGcmSiv::new(&[0; 24]);
+    }
+
+    #[test]
+    fn test_debug_hides_keys() {
+        let (aes_strategy, pmul_strategy) = (AesStrategy::Fallback, PmulStrategy::Nosimd);
+        let gcm = AesGcm::new_with(aes_strategy, pmul_strategy, &[0xab; 24]).unwrap();
+        assert_eq!(
+            format!("{gcm:?}"),
+            "AesGcm { aes_strategy: Fallback, pmul_strategy: Nosimd, key_len: 24, .. }"
+        );
+        let siv = AesGcmSiv::new_with(aes_strategy, pmul_strategy, &[0xab; 32]).unwrap();
+        assert_eq!(
+            format!("{siv:?}"),
+            "AesGcmSiv { aes_strategy: Fallback, pmul_strategy: Nosimd, key_len: 32, .. }"
+        );
+    }
+
+    #[test]
+    fn test_tags_equal() {
+        let tag = [0x5a; TAG_LEN];
+        assert!(tags_equal(&tag, &tag));
+        for i in 0..TAG_LEN {
+            let mut other = tag;
+            other[i] ^= 0x80;
+            assert!(!tags_equal(&tag, &other));
+        }
+    }
+
+    #[bench]
+    fn bench_gcm_seal_4k(b: &mut Bencher) {
+        let gcm = AesGcm::new(&[1; 16]);
+        let mut buffer = vec![0; 4096];
+        b.bytes = buffer.len() as u64;
+        b.iter(|| gcm.seal_in_place(&[2; NONCE_LEN], &[], black_box(&mut buffer)));
+    }
+
+    #[bench]
+    fn bench_siv_seal_4k(b: &mut Bencher) {
+        let siv = AesGcmSiv::new(&[1; 16]);
+        let mut buffer = vec![0; 4096];
+        b.bytes = buffer.len() as u64;
+        b.iter(|| siv.seal_in_place(&[2; NONCE_LEN], &[], black_box(&mut buffer)));
+    }
+}
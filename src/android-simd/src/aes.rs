@@ -1,14 +1,99 @@
-use std::arch::is_aarch64_feature_detected;
+//! AES, from the relinked library: a single round, the block cipher (FIPS-197) with 128-, 192- and
+//! 256-bit keys, and counter mode (SP 800-38A) over buffers of any length.
 
-pub fn aesenc(block: &mut [u8; 16], key: &[u8; 16]) -> &'static str {
-    let block_ptr: *mut u8 = block.as_mut_ptr();
-    let key_ptr: *const u8 = key.as_ptr();
+use crate::dispatch::dispatch;
+use crate::selftest::DisabledStrategies;
+use std::fmt;
 
-    let status = if is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes") {
-        unsafe { aesenc_simd(block_ptr, key_ptr) }
-    } else {
-        unsafe { aesenc_fallback(block_ptr, key_ptr) }
-    };
+/// Size of a block, in bytes.
+pub const BLOCK_LEN: usize = 16;
+
+/// Number of rounds with a 256-bit key, the largest.
+const MAX_ROUNDS: usize = 14;
+
+/// Implementation of AES, in the relinked library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AesStrategy {
+    /// Portable implementation, available on all CPUs.
+    Fallback,
+    /// AES instructions: AESE and AESMC on aarch64 CPUs with the "neon" and "aes" features, AES-NI
+    /// on x86_64 CPUs with the "aes" and "pclmulqdq" features.
+    Simd,
+}
+
+/// Error returned when a strategy isn't supported by the current CPU.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Unsupported(pub AesStrategy);
+
+/// Strategies that failed a self-test.
+static DISABLED: DisabledStrategies = DisabledStrategies::new();
+
+impl AesStrategy {
+    /// All the strategies, whether they are supported by the current CPU or not.
+    pub const ALL: [AesStrategy; 2] = [AesStrategy::Fallback, AesStrategy::Simd];
+
+    /// Name of the strategy, as returned by `aesenc()`.
+    pub fn name(self) -> &'static str {
+        match self {
+            AesStrategy::Fallback => "fallback",
+            AesStrategy::Simd => "simd",
+        }
+    }
+
+    /// Returns whether the current CPU supports this strategy.
+    pub fn is_available(self) -> bool {
+        match self {
+            AesStrategy::Fallback => true,
+            AesStrategy::Simd => {
+                #[cfg(target_arch = "aarch64")]
+                {
+                    use std::arch::is_aarch64_feature_detected;
+                    return is_aarch64_feature_detected!("neon")
+                        && is_aarch64_feature_detected!("aes");
+                }
+                #[cfg(target_arch = "x86_64")]
+                return is_x86_feature_detected!("aes") && is_x86_feature_detected!("pclmulqdq");
+            }
+        }
+    }
+
+    /// Marks this strategy as unusable, so that `aesenc()` and `preferred_strategy()` avoid it.
+    pub fn disable(self) {
+        DISABLED.disable(self as usize);
+    }
+
+    /// Returns whether this strategy was disabled.
+    pub fn is_disabled(self) -> bool {
+        DISABLED.is_disabled(self as usize)
+    }
+}
+
+dispatch! {
+    pub fn aesenc(block: &mut [u8; 16], key: &[u8; 16]) -> &'static str {
+        aarch64["neon", "aes"] if !AesStrategy::Simd.is_disabled() => unsafe aesenc_strategy_simd,
+        x86["aes", "pclmulqdq"] if !AesStrategy::Simd.is_disabled() => unsafe aesenc_strategy_simd,
+        _ => aesenc_strategy_fallback,
+    }
+}
+
+fn aesenc_strategy_fallback(block: &mut [u8; 16], key: &[u8; 16]) -> &'static str {
+    status_name(unsafe { aesenc_fallback(block.as_mut_ptr(), key.as_ptr()) })
+}
+
+#[cfg_attr(
+    target_arch = "aarch64",
+    target_feature(enable = "neon", enable = "aes")
+)]
+#[cfg_attr(
+    target_arch = "x86_64",
+    target_feature(enable = "aes", enable = "pclmulqdq")
+)]
+unsafe fn aesenc_strategy_simd(block: &mut [u8; 16], key: &[u8; 16]) -> &'static str {
+    status_name(aesenc_simd(block.as_mut_ptr(), key.as_ptr()))
+}
+
+/// Name of the strategy that ran, according to the status returned by the relinked library.
+fn status_name(status: u32) -> &'static str {
     match status {
         1 => "fallback",
         2 => "simd",
@@ -16,22 +101,249 @@ pub fn aesenc(block: &mut [u8; 16], key: &[u8; 16]) -> &'static str {
     }
 }
 
+/// Computes an AES round with the given strategy, if the current CPU supports it.
+pub fn aesenc_with(
+    strategy: AesStrategy,
+    block: &mut [u8; 16],
+    key: &[u8; 16],
+) -> Result<(), Unsupported> {
+    if !strategy.is_available() {
+        return Err(Unsupported(strategy));
+    }
+    let block_ptr: *mut u8 = block.as_mut_ptr();
+    let key_ptr: *const u8 = key.as_ptr();
+    match strategy {
+        AesStrategy::Fallback => unsafe { aesenc_fallback(block_ptr, key_ptr) },
+        AesStrategy::Simd => unsafe { aesenc_simd(block_ptr, key_ptr) },
+    };
+    Ok(())
+}
+
+/// Returns the fastest strategy that the current CPU supports and that isn't disabled.
+pub fn preferred_strategy() -> AesStrategy {
+    if AesStrategy::Simd.is_available() && !AesStrategy::Simd.is_disabled() {
+        AesStrategy::Simd
+    } else {
+        AesStrategy::Fallback
+    }
+}
+
+/// AES block cipher, with an expanded 128-, 192- or 256-bit key.
+#[derive(Clone)]
+pub struct Aes {
+    strategy: AesStrategy,
+    rounds: usize,
+    /// Round keys of the cipher.
+    enc_keys: [[u8; BLOCK_LEN]; MAX_ROUNDS + 1],
+    /// Round keys of the equivalent inverse cipher, in the order of decryption.
+    dec_keys: [[u8; BLOCK_LEN]; MAX_ROUNDS + 1],
+}
+
+impl Aes {
+    /// Expands the key, with the strategy selected by `preferred_strategy()`.
+    ///
+    /// Panics if the key isn't 16, 24 or 32 bytes long.
+    pub fn new(key: &[u8]) -> Self {
+        Self::new_with(preferred_strategy(), key).expect("Preferred strategy is unsupported")
+    }
+
+    /// Expands the key with the given strategy, if the current CPU supports it.
+    ///
+    /// Panics if the key isn't 16, 24 or 32 bytes long.
+    pub fn new_with(strategy: AesStrategy, key: &[u8]) -> Result<Self, Unsupported> {
+        assert!(
+            matches!(key.len(), 16 | 24 | 32),
+            "Invalid AES key length: {}",
+            key.len()
+        );
+        if !strategy.is_available() {
+            return Err(Unsupported(strategy));
+        }
+        let mut result = Aes {
+            strategy,
+            rounds: key.len() / 4 + 6,
+            enc_keys: [[0; BLOCK_LEN]; MAX_ROUNDS + 1],
+            dec_keys: [[0; BLOCK_LEN]; MAX_ROUNDS + 1],
+        };
+        let key_ptr: *const u8 = key.as_ptr();
+        let enc_ptr: *mut [u8; BLOCK_LEN] = result.enc_keys.as_mut_ptr();
+        let dec_ptr: *mut [u8; BLOCK_LEN] = result.dec_keys.as_mut_ptr();
+        // Safety: the key length is valid, so the library writes at most MAX_ROUNDS + 1 round keys.
+        match strategy {
+            AesStrategy::Fallback => unsafe {
+                aes_expand_key_fallback(key_ptr, key.len(), enc_ptr, dec_ptr)
+            },
+            AesStrategy::Simd => unsafe {
+                aes_expand_key_simd(key_ptr, key.len(), enc_ptr, dec_ptr)
+            },
+        };
+        Ok(result)
+    }
+
+    /// Returns the strategy of the cipher.
+    pub fn strategy(&self) -> AesStrategy {
+        self.strategy
+    }
+
+    /// Returns the length of the key, in bytes.
+    pub fn key_len(&self) -> usize {
+        (self.rounds - 6) * 4
+    }
+
+    /// Encrypts a block in place.
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCK_LEN]) {
+        let block_ptr: *mut u8 = block.as_mut_ptr();
+        let keys_ptr: *const [u8; BLOCK_LEN] = self.enc_keys.as_ptr();
+        // Safety: the library reads rounds + 1 <= MAX_ROUNDS + 1 round keys.
+        match self.strategy {
+            AesStrategy::Fallback => unsafe {
+                aes_encrypt_fallback(block_ptr, keys_ptr, self.rounds)
+            },
+            AesStrategy::Simd => unsafe { aes_encrypt_simd(block_ptr, keys_ptr, self.rounds) },
+        };
+    }
+
+    /// Decrypts a block in place.
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_LEN]) {
+        let block_ptr: *mut u8 = block.as_mut_ptr();
+        let keys_ptr: *const [u8; BLOCK_LEN] = self.dec_keys.as_ptr();
+        // Safety: the library reads rounds + 1 <= MAX_ROUNDS + 1 round keys.
+        match self.strategy {
+            AesStrategy::Fallback => unsafe {
+                aes_decrypt_fallback(block_ptr, keys_ptr, self.rounds)
+            },
+            AesStrategy::Simd => unsafe { aes_decrypt_simd(block_ptr, keys_ptr, self.rounds) },
+        };
+    }
+
+    /// Encrypts or decrypts data in place in counter mode: XORs it with the encryption of the
+    /// successive counter blocks, starting at `counter`.
+    ///
+    /// The counter is a 128-bit big-endian integer, which wraps around. It is advanced past the
+    /// blocks that were used, including a partial last block, so that consecutive calls continue
+    /// the keystream only if all but the last have a length multiple of `BLOCK_LEN`.
+    pub fn apply_ctr(&self, counter: &mut [u8; BLOCK_LEN], data: &mut [u8]) {
+        match self.strategy {
+            AesStrategy::Fallback => self.ctr_with(aes_ctr_fallback, counter, data),
+            AesStrategy::Simd => self.ctr_with(aes_ctr_simd, counter, data),
+        }
+    }
+
+    /// Same as `apply_ctr()`, but only the first 32 bits of the counter are incremented, as a
+    /// little-endian integer that wraps around, like AES-GCM-SIV does (RFC 8452, section 4).
+    pub fn apply_ctr32_le(&self, counter: &mut [u8; BLOCK_LEN], data: &mut [u8]) {
+        match self.strategy {
+            AesStrategy::Fallback => self.ctr_with(aes_ctr32_le_fallback, counter, data),
+            AesStrategy::Simd => self.ctr_with(aes_ctr32_le_simd, counter, data),
+        }
+    }
+
+    fn ctr_with(
+        &self,
+        ctr: unsafe extern "C" fn(*const [u8; BLOCK_LEN], usize, *mut u8, *mut u8, usize) -> u32,
+        counter: &mut [u8; BLOCK_LEN],
+        data: &mut [u8],
+    ) {
+        let keys_ptr: *const [u8; BLOCK_LEN] = self.enc_keys.as_ptr();
+        let counter_ptr: *mut u8 = counter.as_mut_ptr();
+        let data_ptr: *mut u8 = data.as_mut_ptr();
+        // Safety: the library reads rounds + 1 <= MAX_ROUNDS + 1 round keys, and data.len() bytes.
+        unsafe { ctr(keys_ptr, self.rounds, counter_ptr, data_ptr, data.len()) };
+    }
+}
+
+// The round keys are secret, so they must not end up in the logs.
+impl fmt::Debug for Aes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Aes")
+            .field("strategy", &self.strategy)
+            .field("key_len", &self.key_len())
+            .finish_non_exhaustive()
+    }
+}
+
 #[link(name = "fallback")]
 extern "C" {
     fn aesenc_fallback(block: *mut u8, key: *const u8) -> u32;
+    fn aes_expand_key_fallback(
+        key: *const u8,
+        key_len: usize,
+        enc_keys: *mut [u8; BLOCK_LEN],
+        dec_keys: *mut [u8; BLOCK_LEN],
+    ) -> u32;
+    fn aes_encrypt_fallback(
+        block: *mut u8,
+        round_keys: *const [u8; BLOCK_LEN],
+        rounds: usize,
+    ) -> u32;
+    fn aes_decrypt_fallback(
+        block: *mut u8,
+        round_keys: *const [u8; BLOCK_LEN],
+        rounds: usize,
+    ) -> u32;
+    fn aes_ctr_fallback(
+        round_keys: *const [u8; BLOCK_LEN],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32;
+    fn aes_ctr32_le_fallback(
+        round_keys: *const [u8; BLOCK_LEN],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32;
 }
 
 #[link(name = "simd")]
 extern "C" {
     fn aesenc_simd(block: *mut u8, key: *const u8) -> u32;
+    fn aes_expand_key_simd(
+        key: *const u8,
+        key_len: usize,
+        enc_keys: *mut [u8; BLOCK_LEN],
+        dec_keys: *mut [u8; BLOCK_LEN],
+    ) -> u32;
+    fn aes_encrypt_simd(block: *mut u8, round_keys: *const [u8; BLOCK_LEN], rounds: usize) -> u32;
+    fn aes_decrypt_simd(block: *mut u8, round_keys: *const [u8; BLOCK_LEN], rounds: usize) -> u32;
+    fn aes_ctr_simd(
+        round_keys: *const [u8; BLOCK_LEN],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32;
+    fn aes_ctr32_le_simd(
+        round_keys: *const [u8; BLOCK_LEN],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32;
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::testutil::{bench_strategy, from_hex};
     use std::hint::black_box;
     use test::Bencher;
 
+    /// Example vectors of FIPS-197, Appendix C: the key is the bytes 0, 1, 2, etc. and the
+    /// plaintext is 00112233445566778899aabbccddeeff.
+    const FIPS197_VECTORS: [(usize, [u8; BLOCK_LEN]); 3] = [
+        (16, from_hex("69c4e0d86a7b0430d8cdb78070b4c55a")),
+        (24, from_hex("dda97ca4864cdfe06eaf70a0ec0d7191")),
+        (32, from_hex("8ea2b7ca516745bfeafc49904b496089")),
+    ];
+    const FIPS197_PLAINTEXT: [u8; BLOCK_LEN] = from_hex("00112233445566778899aabbccddeeff");
+
+    fn fips197_key(key_len: usize) -> Vec<u8> {
+        (0..key_len as u8).collect()
+    }
+
     fn aesenc_fallback_wrapper(block: &mut [u8; 16], key: &[u8; 16]) {
         let block_ptr: *mut u8 = block.as_mut_ptr();
         let key_ptr: *const u8 = key.as_ptr();
@@ -44,6 +356,7 @@ pub mod tests {
         unsafe { aesenc_simd(block_ptr, key_ptr) };
     }
 
+    #[cfg(target_arch = "aarch64")]
     #[target_feature(enable = "neon", enable = "aes")]
     unsafe fn aesenc_direct(block: &mut [u8; 16], key: &[u8; 16]) {
         use std::arch::aarch64::{uint8x16_t, vaeseq_u8, vaesmcq_u8, vdupq_n_u8, veorq_u8};
@@ -60,6 +373,17 @@ pub mod tests {
         *block = transmute(simd_block);
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "aes")]
+    unsafe fn aesenc_direct(block: &mut [u8; 16], key: &[u8; 16]) {
+        use std::arch::x86_64::{__m128i, _mm_aesenc_si128};
+        use std::mem::transmute;
+
+        let simd_block: __m128i = transmute(*block);
+        let simd_key: __m128i = transmute(*key);
+        *block = transmute::<__m128i, [u8; 16]>(_mm_aesenc_si128(simd_block, simd_key));
+    }
+
     #[test]
     fn test_aesenc() {
         let mut block = [1; 16];
@@ -68,6 +392,18 @@ pub mod tests {
         assert_eq!(block, [0x7e; 16]);
     }
 
+    #[test]
+    fn test_aesenc_with() {
+        for strategy in AesStrategy::ALL {
+            let mut block = [1; 16];
+            let key = [2; 16];
+            match aesenc_with(strategy, &mut block, &key) {
+                Ok(()) => assert_eq!(block, [0x7e; 16]),
+                Err(e) => assert_eq!(e, Unsupported(strategy)),
+            }
+        }
+    }
+
     #[test]
     fn test_fallback() {
         let mut block = [1; 16];
@@ -92,6 +428,166 @@ pub mod tests {
         assert_eq!(block, [0x7e; 16]);
     }
 
+    #[test]
+    fn test_aes_fips197() {
+        for strategy in AesStrategy::ALL {
+            for (key_len, ciphertext) in FIPS197_VECTORS {
+                let aes = match Aes::new_with(strategy, &fips197_key(key_len)) {
+                    Ok(aes) => aes,
+                    Err(e) => {
+                        assert_eq!(e, Unsupported(strategy));
+                        continue;
+                    }
+                };
+                let mut block = FIPS197_PLAINTEXT;
+                aes.encrypt_block(&mut block);
+                assert_eq!(block, ciphertext);
+                aes.decrypt_block(&mut block);
+                assert_eq!(block, FIPS197_PLAINTEXT);
+            }
+        }
+    }
+
+    #[test]
+    fn test_aes_strategies_agree() {
+        let key: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(37) ^ 5).collect();
+        for key_len in [16, 24, 32] {
+            let fallback = Aes::new_with(AesStrategy::Fallback, &key[..key_len]).unwrap();
+            let aes = Aes::new(&key[..key_len]);
+            assert_eq!(aes.enc_keys, fallback.enc_keys);
+            assert_eq!(aes.dec_keys, fallback.dec_keys);
+
+            let mut block = [0x5a; BLOCK_LEN];
+            for _ in 0..100 {
+                let mut expected = block;
+                fallback.encrypt_block(&mut expected);
+                aes.encrypt_block(&mut block);
+                assert_eq!(block, expected);
+            }
+        }
+    }
+
+    /// Example vector of SP 800-38A, F.5.1: CTR-AES128.Encrypt.
+    #[test]
+    fn test_aes_ctr_sp800_38a() {
+        let key: [u8; 16] = from_hex("2b7e151628aed2a6abf7158809cf4f3c");
+        let plaintext: [[u8; BLOCK_LEN]; 4] = [
+            from_hex("6bc1bee22e409f96e93d7e117393172a"),
+            from_hex("ae2d8a571e03ac9c9eb76fac45af8e51"),
+            from_hex("30c81c46a35ce411e5fbc1191a0a52ef"),
+            from_hex("f69f2445df4f9b17ad2b417be66c3710"),
+        ];
+        let ciphertext: [[u8; BLOCK_LEN]; 4] = [
+            from_hex("874d6191b620e3261bef6864990db6ce"),
+            from_hex("9806f66b7970fdff8617187bb9fffdff"),
+            from_hex("5ae4df3edbd5d35e5b4f09020db03eab"),
+            from_hex("1e031dda2fbe03d1792170a0f3009cee"),
+        ];
+        for strategy in AesStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+            let aes = Aes::new_with(strategy, &key).unwrap();
+            let mut counter = from_hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+            let mut data = plaintext.concat();
+            aes.apply_ctr(&mut counter, &mut data);
+            assert_eq!(data, ciphertext.concat());
+            assert_eq!(counter, from_hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdff03"));
+        }
+    }
+
+    /// Compares counter mode with the encryption of each counter block, on lengths that end in
+    /// every position of the interleaved blocks.
+    #[test]
+    fn test_aes_ctr_lengths() {
+        let key: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(37) ^ 5).collect();
+        let plaintext: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+        for strategy in AesStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+            let aes = Aes::new_with(strategy, &key).unwrap();
+            for len in 0..plaintext.len() {
+                let start = u128::MAX - 3;
+                let mut expected = plaintext[..len].to_vec();
+                for (i, chunk) in expected.chunks_mut(BLOCK_LEN).enumerate() {
+                    let mut block = start.wrapping_add(i as u128).to_be_bytes();
+                    aes.encrypt_block(&mut block);
+                    chunk.iter_mut().zip(block).for_each(|(x, k)| *x ^= k);
+                }
+
+                let mut counter = start.to_be_bytes();
+                let mut data = plaintext[..len].to_vec();
+                aes.apply_ctr(&mut counter, &mut data);
+                assert_eq!(data, expected, "{strategy:?} {len}");
+                let blocks = plaintext[..len].chunks(BLOCK_LEN).len();
+                assert_eq!(counter, start.wrapping_add(blocks as u128).to_be_bytes());
+            }
+        }
+    }
+
+    /// Same as `test_aes_ctr_lengths()` with the 32-bit little-endian counter, which wraps without
+    /// carrying into the rest of the block.
+    #[test]
+    fn test_aes_ctr32_le_lengths() {
+        let key: Vec<u8> = (0..32u8).map(|i| i.wrapping_mul(37) ^ 5).collect();
+        let plaintext: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+        let nonce = [0xa5; BLOCK_LEN - 4];
+        let counter_block = |low: u32| {
+            let mut block = [0; BLOCK_LEN];
+            block[..4].copy_from_slice(&low.to_le_bytes());
+            block[4..].copy_from_slice(&nonce);
+            block
+        };
+        for strategy in AesStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+            let aes = Aes::new_with(strategy, &key).unwrap();
+            for len in 0..plaintext.len() {
+                let start = u32::MAX - 3;
+                let mut expected = plaintext[..len].to_vec();
+                for (i, chunk) in expected.chunks_mut(BLOCK_LEN).enumerate() {
+                    let mut block = counter_block(start.wrapping_add(i as u32));
+                    aes.encrypt_block(&mut block);
+                    chunk.iter_mut().zip(block).for_each(|(x, k)| *x ^= k);
+                }
+
+                let mut counter = counter_block(start);
+                let mut data = plaintext[..len].to_vec();
+                aes.apply_ctr32_le(&mut counter, &mut data);
+                assert_eq!(data, expected, "{strategy:?} {len}");
+                let blocks = plaintext[..len].chunks(BLOCK_LEN).len();
+                assert_eq!(counter, counter_block(start.wrapping_add(blocks as u32)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_aes_ctr_continued() {
+        let aes = Aes::new(&fips197_key(16));
+        let plaintext: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        let mut expected = plaintext.clone();
+        aes.apply_ctr(&mut [0; BLOCK_LEN], &mut expected);
+        for split in (0..plaintext.len()).step_by(48) {
+            let mut counter = [0; BLOCK_LEN];
+            let mut data = plaintext.clone();
+            let (head, tail) = data.split_at_mut(split);
+            aes.apply_ctr(&mut counter, head);
+            aes.apply_ctr(&mut counter, tail);
+            assert_eq!(data, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid AES key length: 20")]
+    fn test_aes_invalid_key() {
+        Aes::new(&[0; 20]);
+    }
+
+    #[test]
+    fn test_aes_debug_hides_keys() {
+        for key_len in [16, 24, 32] {
+            let aes = Aes::new_with(AesStrategy::Fallback, &fips197_key(key_len)).unwrap();
+            assert_eq!(aes.key_len(), key_len);
+            assert_eq!(
+                format!("{aes:?}"),
+                format!("Aes {{ strategy: Fallback, key_len: {key_len}, .. }}")
+            );
+        }
+    }
+
     #[bench]
     fn bench_aesenc(b: &mut Bencher) {
         let mut block = [1; 16];
@@ -119,4 +615,145 @@ pub mod tests {
         let key = [2; 16];
         b.iter(|| unsafe { aesenc_direct(black_box(&mut block), black_box(&key)) });
     }
+
+    fn bench_encrypt(b: &mut Bencher, strategy: AesStrategy, key_len: usize) {
+        bench_strategy(b, strategy.is_available(), |b| {
+            let aes = Aes::new_with(strategy, &fips197_key(key_len)).unwrap();
+            let mut block = FIPS197_PLAINTEXT;
+            b.iter(|| aes.encrypt_block(black_box(&mut block)));
+        });
+    }
+
+    fn bench_decrypt(b: &mut Bencher, strategy: AesStrategy, key_len: usize) {
+        bench_strategy(b, strategy.is_available(), |b| {
+            let aes = Aes::new_with(strategy, &fips197_key(key_len)).unwrap();
+            let mut block = FIPS197_PLAINTEXT;
+            b.iter(|| aes.decrypt_block(black_box(&mut block)));
+        });
+    }
+
+    #[bench]
+    fn bench_aes128_encrypt_fallback(b: &mut Bencher) {
+        bench_encrypt(b, AesStrategy::Fallback, 16);
+    }
+
+    #[bench]
+    fn bench_aes128_encrypt_simd(b: &mut Bencher) {
+        bench_encrypt(b, AesStrategy::Simd, 16);
+    }
+
+    #[bench]
+    fn bench_aes128_decrypt_fallback(b: &mut Bencher) {
+        bench_decrypt(b, AesStrategy::Fallback, 16);
+    }
+
+    #[bench]
+    fn bench_aes128_decrypt_simd(b: &mut Bencher) {
+        bench_decrypt(b, AesStrategy::Simd, 16);
+    }
+
+    #[bench]
+    fn bench_aes256_encrypt_fallback(b: &mut Bencher) {
+        bench_encrypt(b, AesStrategy::Fallback, 32);
+    }
+
+    #[bench]
+    fn bench_aes256_encrypt_simd(b: &mut Bencher) {
+        bench_encrypt(b, AesStrategy::Simd, 32);
+    }
+
+    fn bench_ctr(b: &mut Bencher, strategy: AesStrategy, key_len: usize) {
+        bench_strategy(b, strategy.is_available(), |b| {
+            let aes = Aes::new_with(strategy, &fips197_key(key_len)).unwrap();
+            let mut counter = [0; BLOCK_LEN];
+            let mut data = vec![0x5a; 4096];
+            b.bytes = data.len() as u64;
+            b.iter(|| aes.apply_ctr(black_box(&mut counter), black_box(&mut data)));
+        });
+    }
+
+    #[bench]
+    fn bench_aes128_ctr_4k_fallback(b: &mut Bencher) {
+        bench_ctr(b, AesStrategy::Fallback, 16);
+    }
+
+    #[bench]
+    fn bench_aes128_ctr_4k_simd(b: &mut Bencher) {
+        bench_ctr(b, AesStrategy::Simd, 16);
+    }
+
+    #[bench]
+    fn bench_aes256_ctr_4k_fallback(b: &mut Bencher) {
+        bench_ctr(b, AesStrategy::Fallback, 32);
+    }
+
+    #[bench]
+    fn bench_aes256_ctr_4k_simd(b: &mut Bencher) {
+        bench_ctr(b, AesStrategy::Simd, 32);
+    }
+
+    #[bench]
+    fn bench_aes_expand_key(b: &mut Bencher) {
+        let key = fips197_key(32);
+        b.iter(|| Aes::new(black_box(&key)));
+    }
+
+    /// Table-based fallback, which the relinked library only exports with the "table-baseline"
+    /// feature, as a baseline for the benchmarks of the constant-time one.
+    #[cfg(feature = "table-baseline")]
+    mod table {
+        use super::*;
+
+        #[link(name = "fallback")]
+        extern "C" {
+            fn aesenc_fallback_table(block: *mut u8, key: *const u8) -> u32;
+            fn aes_encrypt_fallback_table(
+                block: *mut u8,
+                round_keys: *const [u8; BLOCK_LEN],
+                rounds: usize,
+            ) -> u32;
+        }
+
+        fn aesenc_fallback_table_wrapper(block: &mut [u8; 16], key: &[u8; 16]) {
+            let block_ptr: *mut u8 = block.as_mut_ptr();
+            let key_ptr: *const u8 = key.as_ptr();
+            unsafe { aesenc_fallback_table(block_ptr, key_ptr) };
+        }
+
+        fn encrypt_block_table(aes: &Aes, block: &mut [u8; BLOCK_LEN]) {
+            let block_ptr: *mut u8 = block.as_mut_ptr();
+            let keys_ptr: *const [u8; BLOCK_LEN] = aes.enc_keys.as_ptr();
+            // Safety: the library reads rounds + 1 <= MAX_ROUNDS + 1 round keys.
+            unsafe { aes_encrypt_fallback_table(block_ptr, keys_ptr, aes.rounds) };
+        }
+
+        #[test]
+        fn test_fallback_table() {
+            let mut block = [1; 16];
+            let key = [2; 16];
+            aesenc_fallback_table_wrapper(&mut block, &key);
+            assert_eq!(block, [0x7e; 16]);
+
+            for (key_len, ciphertext) in FIPS197_VECTORS {
+                let aes = Aes::new_with(AesStrategy::Fallback, &fips197_key(key_len)).unwrap();
+                let mut block = FIPS197_PLAINTEXT;
+                encrypt_block_table(&aes, &mut block);
+                assert_eq!(block, ciphertext);
+            }
+        }
+
+        #[bench]
+        fn bench_aesenc_fallback_table(b: &mut Bencher) {
+            let mut block = [1; 16];
+            let key = [2; 16];
+            b.iter(|| aesenc_fallback_table_wrapper(black_box(&mut block), black_box(&key)));
+        }
+
+        #[bench]
+        fn bench_aes128_encrypt_fallback_table(b: &mut Bencher) {
+            let aes = Aes::new_with(AesStrategy::Fallback, &fips197_key(16)).unwrap();
+            let mut block = FIPS197_PLAINTEXT;
+            b.iter(|| encrypt_block_table(&aes, black_box(&mut block)));
+        }
+    }
 }
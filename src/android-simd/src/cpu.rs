@@ -1,4 +1,4 @@
-use crate::logger::Logger;
+use crate::logger::{Level, LogError, Logger, LoggerExt};
 #[cfg(target_arch = "aarch64")]
 use std::arch::asm;
 use std::collections::HashSet;
@@ -123,35 +123,20 @@ fn parse_mrs() -> u64 {
     aa64isar0
 }
 
-fn format_string_array<S: AsRef<str>>(values: &[S]) -> String {
-    let mut result = String::new();
-    result.push('[');
-    for (i, x) in values.iter().enumerate() {
-        if i != 0 {
-            result.push_str(", ");
-        }
-        result.push_str(x.as_ref());
-    }
-    result.push(']');
-    result
-}
-
-fn display_features<L: Logger>(
-    logger: &L,
+fn display_features(
+    logger: &dyn Logger,
     enabled: &[&str],
     disabled: &[&str],
-) -> Result<(), L::E> {
-    logger.d(format!(
-        "Detected {} enabled features:\n    {}",
-        enabled.len(),
-        format_string_array(enabled)
-    ))?;
-    logger.d(format!(
-        "Detected {} disabled features:\n    {}",
-        disabled.len(),
-        format_string_array(disabled)
-    ))?;
-    Ok(())
+) -> Result<(), LogError> {
+    logger.log_fields(
+        Level::Info,
+        "Detected CPU features",
+        &[
+            ("arch", get_arch_name().into()),
+            ("enabled", enabled.into()),
+            ("disabled", disabled.into()),
+        ],
+    )
 }
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -204,7 +189,7 @@ macro_rules! print_aarch64_features {
     }
 }
 
-pub fn print_cpu_features<L: Logger>(logger: &L) -> Result<(), L::E> {
+pub fn print_cpu_features(logger: &dyn Logger) -> Result<(), LogError> {
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     print_x86_features!(
         logger,
@@ -333,42 +318,42 @@ pub fn print_cpu_features<L: Logger>(logger: &L) -> Result<(), L::E> {
             nibbles = 2 * std::mem::size_of::<usize>(),
             bits = 8 * std::mem::size_of::<usize>(),
         ))?,
-        Err(e) => logger.d(format!("Failed to parse /proc/self/auxv: {:?}", e))?,
+        Err(e) => logger.w(format!("Failed to parse /proc/self/auxv: {:?}", e))?,
     }
 
     match parse_cpuinfo_features() {
         Ok(features) => {
-            let mut sorted = features.iter().collect::<Vec<_>>();
+            let mut sorted = features.iter().map(|x| x.as_str()).collect::<Vec<_>>();
             sorted.sort_unstable();
-            logger.d(format!(
-                "Found {} features in /proc/cpuinfo:\n    {}",
-                sorted.len(),
-                format_string_array(&sorted)
-            ))?;
+            logger.log_fields(
+                Level::Debug,
+                "Found features in /proc/cpuinfo",
+                &[("features", sorted.as_slice().into())],
+            )?;
         }
-        Err(e) => logger.d(format!("Failed to parse /proc/cpuinfo: {:?}", e))?,
+        Err(e) => logger.w(format!("Failed to parse /proc/cpuinfo: {:?}", e))?,
     }
 
     match cat_auxv() {
         Ok(mut map) => {
-            logger.d("Contents of /proc/self/auxv:")?;
+            logger.v("Contents of /proc/self/auxv:")?;
             map.sort_by_key(|(key, _)| *key);
             for (key, value) in map {
-                logger.d(format!(
+                logger.v(format!(
                     "    {key:2} = {value:0nibbles$x} / {value:0bits$b}",
                     nibbles = 2 * std::mem::size_of::<usize>(),
                     bits = 8 * std::mem::size_of::<usize>(),
                 ))?;
             }
         }
-        Err(e) => logger.d(format!("Failed to read /proc/self/auxv: {:?}", e))?,
+        Err(e) => logger.w(format!("Failed to read /proc/self/auxv: {:?}", e))?,
     }
 
     match cat_cpuinfo() {
         Ok(contents) => {
-            logger.d(format!("Contents of /proc/cpuinfo:\n{}", contents))?;
+            logger.v(format!("Contents of /proc/cpuinfo:\n{}", contents))?;
         }
-        Err(e) => logger.d(format!("Failed to read /proc/cpuinfo: {:?}", e))?,
+        Err(e) => logger.w(format!("Failed to read /proc/cpuinfo: {:?}", e))?,
     }
 
     Ok(())
@@ -0,0 +1,597 @@
+//! Cyclic redundancy checks: CRC-32 (IEEE 802.3), CRC-32C (Castagnoli), and the 64-bit CRCs of xz
+//! (ECMA-182 polynomial) and NVMe.
+//!
+//! All these CRCs are reflected: the bits of each byte are processed from the least significant
+//! one, and the register holds the coefficients in the same order. The register starts with all
+//! ones, and is inverted at the end.
+//!
+//! Besides the table-driven fallback, the CRC-32 instructions of x86 (CRC-32C only) and aarch64
+//! (CRC-32 and CRC-32C) update the register directly. For all the CRCs, the data can also be folded
+//! with the carry-less multiplication: multiplying a 16-byte block by x^n modulo the polynomial
+//! moves it n bits forward, onto the following data, until only one block and the bytes after it
+//! remain. These go through the table.
+
+#[cfg(target_arch = "aarch64")]
+use crate::pmul::pmul_strategy_aarch64_neon;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::pmul::pmul_strategy_x86_clmul;
+use crate::selftest::DisabledStrategies;
+
+/// Size of a folded block, in bytes.
+const BLOCK_LEN: usize = 16;
+
+/// Number of blocks folded in parallel.
+const LANES: usize = 4;
+
+/// Strategies that failed a self-test.
+static DISABLED: DisabledStrategies = DisabledStrategies::new();
+
+/// CRC algorithm, defined by its polynomial.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CrcAlgorithm {
+    /// CRC-32 of IEEE 802.3, zlib and PNG, with polynomial 0x04c11db7.
+    Crc32,
+    /// CRC-32C of iSCSI and ext4, with polynomial 0x1edc6f41.
+    Crc32c,
+    /// CRC-64 of xz, with the polynomial 0x42f0e1eba9ea3693 of ECMA-182.
+    Crc64Xz,
+    /// CRC-64 of NVMe, with polynomial 0xad93d23594c93659.
+    Crc64Nvme,
+}
+
+impl CrcAlgorithm {
+    /// All the algorithms.
+    pub const ALL: [CrcAlgorithm; 4] = [
+        CrcAlgorithm::Crc32,
+        CrcAlgorithm::Crc32c,
+        CrcAlgorithm::Crc64Xz,
+        CrcAlgorithm::Crc64Nvme,
+    ];
+
+    /// Name of the algorithm.
+    pub fn name(self) -> &'static str {
+        match self {
+            CrcAlgorithm::Crc32 => "crc32",
+            CrcAlgorithm::Crc32c => "crc32c",
+            CrcAlgorithm::Crc64Xz => "crc64_xz",
+            CrcAlgorithm::Crc64Nvme => "crc64_nvme",
+        }
+    }
+
+    fn params(self) -> &'static Params {
+        match self {
+            CrcAlgorithm::Crc32 => &CRC32,
+            CrcAlgorithm::Crc32c => &CRC32C,
+            CrcAlgorithm::Crc64Xz => &CRC64_XZ,
+            CrcAlgorithm::Crc64Nvme => &CRC64_NVME,
+        }
+    }
+}
+
+static CRC32: Params = Params::new(32, 0x04c11db7);
+static CRC32C: Params = Params::new(32, 0x1edc6f41);
+static CRC64_XZ: Params = Params::new(64, 0x42f0e1eba9ea3693);
+static CRC64_NVME: Params = Params::new(64, 0xad93d23594c93659);
+
+/// Tables and constants of a reflected CRC, derived from its polynomial at compile time.
+struct Params {
+    /// Mask of the bits of the register, which is also its initial value and final inversion.
+    mask: u64,
+    /// Update of the register for each byte value.
+    table: [u64; 256],
+    /// Keys to fold a block by 16 bytes, for its low and high halves.
+    fold_1: [u64; 2],
+    /// Keys to fold a block by `LANES` blocks, for its low and high halves.
+    fold_lanes: [u64; 2],
+}
+
+impl Params {
+    /// Derives the parameters from the polynomial, in normal notation and without its x^width
+    /// term.
+    const fn new(width: u32, poly: u64) -> Self {
+        let reflected = poly.reverse_bits() >> (64 - width);
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u64;
+            let mut j = 0;
+            while j < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ reflected
+                } else {
+                    crc >> 1
+                };
+                j += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+
+        // Folding a block by n bits multiplies its first (low) half by x^(n + 64) and its second
+        // half by x^n. The keys lack a factor x, which the reflected multiplication adds.
+        let n = 8 * BLOCK_LEN as u32;
+        Params {
+            mask: u64::MAX >> (64 - width),
+            table,
+            fold_1: [
+                x_pow_mod(width, poly, n + 63),
+                x_pow_mod(width, poly, n - 1),
+            ],
+            fold_lanes: [
+                x_pow_mod(width, poly, LANES as u32 * n + 63),
+                x_pow_mod(width, poly, LANES as u32 * n - 1),
+            ],
+        }
+    }
+}
+
+/// Computes x^n modulo the polynomial, bit-reflected on 64 bits.
+const fn x_pow_mod(width: u32, poly: u64, n: u32) -> u64 {
+    let modulus = (1 << width) | poly as u128;
+    let mut result: u128 = 1;
+    let mut i = 0;
+    while i < n {
+        result <<= 1;
+        if (result >> width) & 1 != 0 {
+            result ^= modulus;
+        }
+        i += 1;
+    }
+    (result as u64).reverse_bits()
+}
+
+/// Implementation of the CRCs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CrcStrategy {
+    /// Table-driven implementation, available on all CPUs.
+    Table,
+    /// CRC32 instruction, on x86 and x86_64 CPUs with the "sse4.2" feature. It only supports
+    /// CRC-32C.
+    X86Crc32,
+    /// Folding with the PCLMULQDQ instruction, on x86 and x86_64 CPUs with the "sse2" and
+    /// "pclmulqdq" features.
+    X86Clmul,
+    /// CRC32 instructions, on aarch64 CPUs with the "crc" feature. They only support CRC-32 and
+    /// CRC-32C.
+    Aarch64Crc32,
+    /// Folding with the PMULL instruction, on aarch64 CPUs with the "neon" and "aes" features.
+    Aarch64Pmull,
+}
+
+/// Error returned when a strategy isn't supported by the current CPU, or doesn't support the
+/// algorithm.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Unsupported(pub CrcStrategy);
+
+impl CrcStrategy {
+    /// All the strategies, whether they are supported by the current CPU or not.
+    pub const ALL: [CrcStrategy; 5] = [
+        CrcStrategy::Table,
+        CrcStrategy::X86Crc32,
+        CrcStrategy::X86Clmul,
+        CrcStrategy::Aarch64Crc32,
+        CrcStrategy::Aarch64Pmull,
+    ];
+
+    /// Name of the strategy, as returned by `crc_strategy()`.
+    pub fn name(self) -> &'static str {
+        match self {
+            CrcStrategy::Table => "table",
+            CrcStrategy::X86Crc32 => "x86_crc32",
+            CrcStrategy::X86Clmul => "x86_clmul",
+            CrcStrategy::Aarch64Crc32 => "aarch64_crc32",
+            CrcStrategy::Aarch64Pmull => "aarch64_pmull",
+        }
+    }
+
+    /// Returns whether the current CPU supports this strategy.
+    pub fn is_available(self) -> bool {
+        match self {
+            CrcStrategy::Table => true,
+            CrcStrategy::X86Crc32 => {
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                return is_x86_feature_detected!("sse4.2");
+                #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+                return false;
+            }
+            CrcStrategy::X86Clmul => {
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                return is_x86_feature_detected!("sse2") && is_x86_feature_detected!("pclmulqdq");
+                #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+                return false;
+            }
+            CrcStrategy::Aarch64Crc32 => {
+                #[cfg(target_arch = "aarch64")]
+                {
+                    use std::arch::is_aarch64_feature_detected;
+                    return is_aarch64_feature_detected!("crc");
+                }
+                #[cfg(not(target_arch = "aarch64"))]
+                return false;
+            }
+            CrcStrategy::Aarch64Pmull => {
+                #[cfg(target_arch = "aarch64")]
+                {
+                    use std::arch::is_aarch64_feature_detected;
+                    return is_aarch64_feature_detected!("neon")
+                        && is_aarch64_feature_detected!("aes");
+                }
+                #[cfg(not(target_arch = "aarch64"))]
+                return false;
+            }
+        }
+    }
+
+    /// Returns whether this strategy computes the given algorithm, on CPUs where it's available.
+    pub fn supports(self, algorithm: CrcAlgorithm) -> bool {
+        match self {
+            CrcStrategy::X86Crc32 => algorithm == CrcAlgorithm::Crc32c,
+            CrcStrategy::Aarch64Crc32 => {
+                matches!(algorithm, CrcAlgorithm::Crc32 | CrcAlgorithm::Crc32c)
+            }
+            CrcStrategy::Table | CrcStrategy::X86Clmul | CrcStrategy::Aarch64Pmull => true,
+        }
+    }
+
+    /// Marks this strategy as unusable, so that `crc_strategy()` avoids it. The table remains its
+    /// last resort, and `crc_with()` still runs disabled strategies when explicitly asked to.
+    pub fn disable(self) {
+        DISABLED.disable(self as usize);
+    }
+
+    /// Returns whether this strategy was disabled.
+    pub fn is_disabled(self) -> bool {
+        DISABLED.is_disabled(self as usize)
+    }
+}
+
+/// Returns the strategy that `crc_strategy()` selects for the algorithm: the CRC instructions,
+/// then the folding, if the current CPU supports them and they weren't disabled, and the table
+/// otherwise. The folding is slightly faster on long inputs, but falls back to the table below a
+/// block and for the trailing bytes, whereas the CRC instructions are fast at all lengths.
+pub fn preferred_strategy(algorithm: CrcAlgorithm) -> CrcStrategy {
+    [
+        CrcStrategy::Aarch64Crc32,
+        CrcStrategy::X86Crc32,
+        CrcStrategy::Aarch64Pmull,
+        CrcStrategy::X86Clmul,
+    ]
+    .into_iter()
+    .find(|strategy| {
+        strategy.supports(algorithm) && strategy.is_available() && !strategy.is_disabled()
+    })
+    .unwrap_or(CrcStrategy::Table)
+}
+
+/// Computes the CRC of the data, and returns the name of the strategy.
+pub fn crc_strategy(algorithm: CrcAlgorithm, data: &[u8]) -> (u64, &'static str) {
+    let strategy = preferred_strategy(algorithm);
+    let crc = crc_with(strategy, algorithm, data).expect("Preferred strategy is unsupported");
+    (crc, strategy.name())
+}
+
+/// Computes the CRC of the data with the given strategy, if the current CPU supports it and it
+/// supports the algorithm.
+pub fn crc_with(
+    strategy: CrcStrategy,
+    algorithm: CrcAlgorithm,
+    data: &[u8],
+) -> Result<u64, Unsupported> {
+    if !strategy.is_available() || !strategy.supports(algorithm) {
+        return Err(Unsupported(strategy));
+    }
+    let params = algorithm.params();
+    let crc = params.mask;
+    let crc = match strategy {
+        CrcStrategy::Table => update_table(params, crc, data),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        // Safety: target_feature "sse4.2" is available, as checked above.
+        CrcStrategy::X86Crc32 => unsafe { update_x86_crc32c(crc, data) },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        // Safety: target_features "sse2" and "pclmulqdq" are available, as checked above.
+        CrcStrategy::X86Clmul => unsafe { update_x86_clmul(params, crc, data) },
+        #[cfg(target_arch = "aarch64")]
+        // Safety: target_feature "crc" is available, as checked above.
+        CrcStrategy::Aarch64Crc32 => unsafe {
+            if algorithm == CrcAlgorithm::Crc32c {
+                update_aarch64_crc32::<true>(crc, data)
+            } else {
+                update_aarch64_crc32::<false>(crc, data)
+            }
+        },
+        #[cfg(target_arch = "aarch64")]
+        // Safety: target_features "neon" and "aes" are available, as checked above.
+        CrcStrategy::Aarch64Pmull => unsafe { update_aarch64_pmull(params, crc, data) },
+        #[allow(unreachable_patterns)]
+        _ => return Err(Unsupported(strategy)),
+    };
+    Ok(crc ^ params.mask)
+}
+
+fn update_table(params: &Params, mut crc: u64, data: &[u8]) -> u64 {
+    for &byte in data {
+        crc = (crc >> 8) ^ params.table[((crc ^ byte as u64) & 0xff) as usize];
+    }
+    crc
+}
+
+/// Folds the data with the given 64x64-bit carry-less multiplication, `LANES` blocks at a time
+/// while there are enough blocks, then one block at a time.
+#[inline(always)]
+fn update_fold(params: &Params, crc: u64, data: &[u8], mul: impl Fn(u64, u64) -> u128) -> u64 {
+    if data.len() < BLOCK_LEN {
+        return update_table(params, crc, data);
+    }
+    let fold = |block: u128, keys: &[u64; 2]| {
+        mul(block as u64, keys[0]) ^ mul((block >> 64) as u64, keys[1])
+    };
+    let (mut blocks, tail) = data.as_chunks::<BLOCK_LEN>();
+
+    // The register is added to the first bytes of the data.
+    let mut state = if blocks.len() >= 2 * LANES {
+        let mut lanes = [0; LANES];
+        for (lane, block) in lanes.iter_mut().zip(blocks) {
+            *lane = u128::from_le_bytes(*block);
+        }
+        lanes[0] ^= crc as u128;
+        blocks = &blocks[LANES..];
+        while blocks.len() >= LANES {
+            for (lane, block) in lanes.iter_mut().zip(blocks) {
+                *lane = fold(*lane, &params.fold_lanes) ^ u128::from_le_bytes(*block);
+            }
+            blocks = &blocks[LANES..];
+        }
+        lanes[1..]
+            .iter()
+            .fold(lanes[0], |state, lane| fold(state, &params.fold_1) ^ lane)
+    } else {
+        let state = u128::from_le_bytes(blocks[0]) ^ crc as u128;
+        blocks = &blocks[1..];
+        state
+    };
+    for block in blocks {
+        state = fold(state, &params.fold_1) ^ u128::from_le_bytes(*block);
+    }
+
+    let crc = update_table(params, 0, &state.to_le_bytes());
+    update_table(params, crc, tail)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse4.2")]
+unsafe fn update_x86_crc32c(crc: u64, data: &[u8]) -> u64 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{_mm_crc32_u32, _mm_crc32_u8};
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{_mm_crc32_u64, _mm_crc32_u8};
+
+    // Safety: target_feature "sse4.2" is available in this function.
+    #[cfg(target_arch = "x86")]
+    let (crc, tail) = {
+        let (words, tail) = data.as_chunks::<4>();
+        let mut crc = crc as u32;
+        for word in words {
+            crc = _mm_crc32_u32(crc, u32::from_le_bytes(*word));
+        }
+        (crc, tail)
+    };
+    #[cfg(target_arch = "x86_64")]
+    let (crc, tail) = {
+        let (words, tail) = data.as_chunks::<8>();
+        let mut crc = crc;
+        for word in words {
+            crc = _mm_crc32_u64(crc, u64::from_le_bytes(*word));
+        }
+        (crc as u32, tail)
+    };
+
+    let mut crc = crc;
+    for &byte in tail {
+        crc = _mm_crc32_u8(crc, byte);
+    }
+    crc as u64
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2", enable = "pclmulqdq")]
+unsafe fn update_x86_clmul(params: &Params, crc: u64, data: &[u8]) -> u64 {
+    // Safety: target_features "sse2" and "pclmulqdq" are available in this function.
+    update_fold(params, crc, data, |a, b| pmul_strategy_x86_clmul(a, b).0)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "crc")]
+unsafe fn update_aarch64_crc32<const CASTAGNOLI: bool>(crc: u64, data: &[u8]) -> u64 {
+    use std::arch::aarch64::{__crc32b, __crc32cb, __crc32cd, __crc32d};
+
+    let (words, tail) = data.as_chunks::<8>();
+    let mut crc = crc as u32;
+    // Safety: target_feature "crc" is available in this function.
+    for word in words {
+        let word = u64::from_le_bytes(*word);
+        crc = if CASTAGNOLI {
+            __crc32cd(crc, word)
+        } else {
+            __crc32d(crc, word)
+        };
+    }
+    for &byte in tail {
+        crc = if CASTAGNOLI {
+            __crc32cb(crc, byte)
+        } else {
+            __crc32b(crc, byte)
+        };
+    }
+    crc as u64
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon", enable = "aes")]
+unsafe fn update_aarch64_pmull(params: &Params, crc: u64, data: &[u8]) -> u64 {
+    // Safety: target_features "neon" and "aes" are available in this function.
+    update_fold(params, crc, data, |a, b| pmul_strategy_aarch64_neon(a, b).0)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::testutil::{bench_strategy, random_u64s, RANDOM_SEED};
+    use std::hint::black_box;
+    use test::Bencher;
+
+    /// Check values of the CRC catalogue (https://reveng.sourceforge.io/crc-catalogue/), i.e.
+    /// the CRCs of "123456789".
+    const CHECK_VALUES: [(CrcAlgorithm, u64); 4] = [
+        (CrcAlgorithm::Crc32, 0xcbf43926),
+        (CrcAlgorithm::Crc32c, 0xe3069283),
+        (CrcAlgorithm::Crc64Xz, 0x995dc9bbdf1939fa),
+        (CrcAlgorithm::Crc64Nvme, 0xae8b14860a799888),
+    ];
+
+    fn random_bytes(count: usize) -> Vec<u8> {
+        random_u64s(count, RANDOM_SEED)
+            .into_iter()
+            .map(|word| word as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_check_values() {
+        for (algorithm, expected) in CHECK_VALUES {
+            for strategy in CrcStrategy::ALL {
+                match crc_with(strategy, algorithm, b"123456789") {
+                    Ok(crc) => assert_eq!(crc, expected, "{algorithm:?} {strategy:?}"),
+                    Err(e) => {
+                        assert!(!strategy.is_available() || !strategy.supports(algorithm));
+                        assert_eq!(e, Unsupported(strategy));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_crc_strategy() {
+        for (algorithm, expected) in CHECK_VALUES {
+            let (crc, strategy) = crc_strategy(algorithm, b"123456789");
+            assert_eq!(crc, expected, "{algorithm:?}");
+            assert_eq!(strategy, preferred_strategy(algorithm).name());
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if is_x86_feature_detected!("sse4.2") {
+            assert_eq!(
+                preferred_strategy(CrcAlgorithm::Crc32c),
+                CrcStrategy::X86Crc32
+            );
+        }
+    }
+
+    #[test]
+    fn test_crc_zlib() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let (crc, _) = crc_strategy(CrcAlgorithm::Crc32, &data);
+        assert_eq!(crc, 0x29058c73);
+    }
+
+    /// All the strategies match the table, for all the lengths of the folded lanes and of the
+    /// tail.
+    #[test]
+    fn test_cross_check() {
+        let data = random_bytes(3 * LANES * BLOCK_LEN + BLOCK_LEN);
+        for algorithm in CrcAlgorithm::ALL {
+            for len in 0..=data.len() {
+                let expected = crc_with(CrcStrategy::Table, algorithm, &data[..len]).unwrap();
+                for strategy in CrcStrategy::ALL {
+                    if let Ok(crc) = crc_with(strategy, algorithm, &data[..len]) {
+                        assert_eq!(crc, expected, "{algorithm:?} {strategy:?} {len}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_supports() {
+        assert!(!CrcStrategy::X86Crc32.supports(CrcAlgorithm::Crc32));
+        assert!(!CrcStrategy::Aarch64Crc32.supports(CrcAlgorithm::Crc64Xz));
+        assert_eq!(
+            crc_with(CrcStrategy::X86Crc32, CrcAlgorithm::Crc64Nvme, b""),
+            Err(Unsupported(CrcStrategy::X86Crc32))
+        );
+        for algorithm in CrcAlgorithm::ALL {
+            assert!(CrcStrategy::Table.supports(algorithm));
+        }
+    }
+
+    fn bench_crc_with(b: &mut Bencher, strategy: CrcStrategy, algorithm: CrcAlgorithm) {
+        let available = strategy.is_available() && strategy.supports(algorithm);
+        bench_strategy(b, available, |b| {
+            let data = random_bytes(4096);
+            b.bytes = data.len() as u64;
+            b.iter(|| crc_with(strategy, algorithm, black_box(&data)));
+        });
+    }
+
+    #[bench]
+    fn bench_crc32_table(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::Table, CrcAlgorithm::Crc32);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_crc32_x86_clmul(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::X86Clmul, CrcAlgorithm::Crc32);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[bench]
+    fn bench_crc32_aarch64_crc32(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::Aarch64Crc32, CrcAlgorithm::Crc32);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[bench]
+    fn bench_crc32_aarch64_pmull(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::Aarch64Pmull, CrcAlgorithm::Crc32);
+    }
+
+    #[bench]
+    fn bench_crc32c_table(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::Table, CrcAlgorithm::Crc32c);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_crc32c_x86_crc32(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::X86Crc32, CrcAlgorithm::Crc32c);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_crc32c_x86_clmul(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::X86Clmul, CrcAlgorithm::Crc32c);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[bench]
+    fn bench_crc32c_aarch64_crc32(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::Aarch64Crc32, CrcAlgorithm::Crc32c);
+    }
+
+    #[bench]
+    fn bench_crc64_xz_table(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::Table, CrcAlgorithm::Crc64Xz);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_crc64_xz_x86_clmul(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::X86Clmul, CrcAlgorithm::Crc64Xz);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[bench]
+    fn bench_crc64_xz_aarch64_pmull(b: &mut Bencher) {
+        bench_crc_with(b, CrcStrategy::Aarch64Pmull, CrcAlgorithm::Crc64Xz);
+    }
+}
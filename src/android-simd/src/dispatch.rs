@@ -0,0 +1,226 @@
+//! Runtime dispatch between implementations that require different CPU features.
+//!
+//! The `dispatch!` macro defines a safe function that picks, on its first call, the first candidate
+//! implementation whose target features are detected, and caches the function pointer for the next
+//! calls. Disabling a strategy invalidates all the caches, so that the dispatchers resolve their
+//! implementation again.
+
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Incremented whenever a resolution may have a different outcome, i.e. when a strategy is
+/// disabled.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Invalidates the function pointers cached by all the dispatchers.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Function pointer cached by a dispatcher, with the generation at which it was resolved.
+pub struct Cache {
+    pointer: AtomicPtr<()>,
+    generation: AtomicUsize,
+    /// Serializes the resolutions, so that a resolution from a previous generation can't
+    /// overwrite a more recent one.
+    lock: Mutex<()>,
+}
+
+impl Cache {
+    pub const fn new() -> Self {
+        Cache {
+            pointer: AtomicPtr::new(std::ptr::null_mut()),
+            // Never equal to the current generation, so that the first call resolves the pointer.
+            generation: AtomicUsize::new(usize::MAX),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns the cached pointer, unless it was resolved before the last invalidation.
+    #[inline]
+    pub fn get(&self) -> Option<*mut ()> {
+        if self.generation.load(Ordering::Acquire) == GENERATION.load(Ordering::Acquire) {
+            Some(self.pointer.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    /// Resolves the pointer, caches it and returns it.
+    #[cold]
+    pub fn resolve(&self, resolve: impl FnOnce() -> *mut ()) -> *mut () {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        // The generation is read before the disabled strategies, so that disabling one during the
+        // resolution makes the result stale.
+        let generation = GENERATION.load(Ordering::Acquire);
+        let pointer = resolve();
+        self.pointer.store(pointer, Ordering::Relaxed);
+        self.generation.store(generation, Ordering::Release);
+        pointer
+    }
+}
+
+/// Defines a function that forwards its calls to the first candidate implementation whose target
+/// features the CPU supports, or to the fallback.
+///
+/// Each candidate lists its architecture ("x86", which also covers x86_64, "aarch64" or "arm") with
+/// the features to detect, an optional guard, typically that the strategy isn't disabled, and the
+/// implementation. The implementations have the signature of the dispatcher, and may be
+/// `#[target_feature]` functions: the `unsafe` keyword before each candidate asserts that the
+/// listed features are enough to call it safely. The candidates of the other architectures are
+/// ignored.
+///
+/// ```ignore
+/// dispatch! {
+///     pub fn pmul_strategy(a: u64, b: u64) -> (u128, &'static str) {
+///         x86["sse2", "pclmulqdq"] if !PmulStrategy::X86Clmul.is_disabled()
+///             => unsafe pmul_strategy_x86_clmul,
+///         _ => pmul_strategy_nosimd,
+///     }
+/// }
+/// ```
+macro_rules! dispatch {
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty {
+            $(
+                $arch:ident[$($feature:tt),+ $(,)?] $(if $guard:expr)? => unsafe $candidate:expr,
+            )*
+            _ => $fallback:expr $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($arg: $ty),*) -> $ret {
+            type Implementation = unsafe fn($($ty),*) -> $ret;
+            static CACHE: $crate::dispatch::Cache = $crate::dispatch::Cache::new();
+
+            fn resolve() -> Implementation {
+                $(
+                    $crate::dispatch::dispatch!(
+                        @candidate $arch[$($feature),+] $(if $guard)? => $candidate
+                    );
+                )*
+                $fallback
+            }
+
+            let pointer = CACHE
+                .get()
+                .unwrap_or_else(|| CACHE.resolve(|| resolve() as *mut ()));
+            // Safety: the cache only holds pointers returned by resolve(), of type Implementation.
+            let implementation =
+                unsafe { std::mem::transmute::<*mut (), Implementation>(pointer) };
+            // Safety: the candidates were only selected if their target features are available, as
+            // asserted by the caller of dispatch!.
+            unsafe { implementation($($arg),*) }
+        }
+    };
+    (@candidate x86[$($feature:tt),+] $(if $guard:expr)? => $candidate:expr) => {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if $(std::arch::is_x86_feature_detected!($feature))&&+ $(&& $guard)? {
+                return $candidate;
+            }
+        }
+    };
+    (@candidate aarch64[$($feature:tt),+] $(if $guard:expr)? => $candidate:expr) => {
+        #[cfg(target_arch = "aarch64")]
+        {
+            if $(std::arch::is_aarch64_feature_detected!($feature))&&+ $(&& $guard)? {
+                return $candidate;
+            }
+        }
+    };
+    (@candidate arm[$($feature:tt),+] $(if $guard:expr)? => $candidate:expr) => {
+        #[cfg(target_arch = "arm")]
+        {
+            if $(std::arch::is_arm_feature_detected!($feature))&&+ $(&& $guard)? {
+                return $candidate;
+            }
+        }
+    };
+}
+
+pub(crate) use dispatch;
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    static RESOLUTIONS: AtomicUsize = AtomicUsize::new(0);
+    static SIMD_DISABLED: AtomicBool = AtomicBool::new(false);
+
+    fn double_fallback(x: u64) -> (u64, &'static str) {
+        (2 * x, "fallback")
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "sse2")]
+    unsafe fn double_simd(x: u64) -> (u64, &'static str) {
+        (2 * x, "simd")
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "neon")]
+    unsafe fn double_simd(x: u64) -> (u64, &'static str) {
+        (2 * x, "simd")
+    }
+
+    /// Counts its resolutions in the guard of the candidate, which is evaluated at each of them.
+    fn simd_enabled() -> bool {
+        RESOLUTIONS.fetch_add(1, Ordering::Relaxed);
+        !SIMD_DISABLED.load(Ordering::Relaxed)
+    }
+
+    dispatch! {
+        fn double(x: u64) -> (u64, &'static str) {
+            x86["sse2"] if simd_enabled() => unsafe double_simd,
+            aarch64["neon"] if simd_enabled() => unsafe double_simd,
+            _ => double_fallback,
+        }
+    }
+
+    dispatch! {
+        fn triple(x: u64) -> u64 {
+            arm["neon"] => unsafe |x: u64| 3 * x,
+            _ => |x: u64| 3 * x,
+        }
+    }
+
+    #[test]
+    fn test_dispatch() {
+        // The other tests may invalidate the caches at any time, so this only checks the pointers
+        // resolved within one generation.
+        let simd = cfg!(any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "aarch64"
+        ));
+        assert_eq!(double(21), (42, if simd { "simd" } else { "fallback" }));
+        let resolutions = RESOLUTIONS.load(Ordering::Relaxed);
+        let generation = GENERATION.load(Ordering::Acquire);
+        assert_eq!(double(4), (8, if simd { "simd" } else { "fallback" }));
+        if GENERATION.load(Ordering::Acquire) == generation {
+            assert_eq!(RESOLUTIONS.load(Ordering::Relaxed), resolutions);
+        }
+
+        SIMD_DISABLED.store(true, Ordering::Relaxed);
+        invalidate();
+        assert_eq!(double(21), (42, "fallback"));
+        assert_eq!(triple(14), 42);
+    }
+
+    #[test]
+    fn test_cache() {
+        let cache = Cache::new();
+        assert_eq!(cache.get(), None);
+        let pointer = double_fallback as fn(u64) -> (u64, &'static str) as *mut ();
+        assert_eq!(cache.resolve(|| pointer), pointer);
+        // Another test may invalidate the caches in between.
+        if let Some(cached) = cache.get() {
+            assert_eq!(cached, pointer);
+        }
+        invalidate();
+        assert_eq!(cache.get(), None);
+    }
+}
@@ -1,44 +1,149 @@
+use crate::dispatch::dispatch;
+use crate::selftest::DisabledStrategies;
 use std::arch::is_aarch64_feature_detected;
 
-pub fn gf256_shamir_split_10(secret: &[u8; 32], output: &mut [u8; 640]) {
-    let secret_ptr = secret as *const [u8; 32];
-    let output_ptr = output.as_mut_ptr();
+/// Implementation of the binary field arithmetic, in the relinked library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Gf2nStrategy {
+    /// Portable implementation, available on all CPUs.
+    Fallback,
+    /// PMULL instruction, on CPUs with the "neon" and "aes" features.
+    Simd,
+}
 
-    if is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes") {
-        unsafe { gf256_shamir_split_10_simd(secret_ptr, output_ptr) }
-    } else {
-        unsafe { gf256_shamir_split_10_fallback(secret_ptr, output_ptr) }
-    };
+/// Error returned when a strategy isn't supported by the current CPU.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Unsupported(pub Gf2nStrategy);
+
+/// Strategies that failed a self-test.
+static DISABLED: DisabledStrategies = DisabledStrategies::new();
+
+impl Gf2nStrategy {
+    /// All the strategies, whether they are supported by the current CPU or not.
+    pub const ALL: [Gf2nStrategy; 2] = [Gf2nStrategy::Fallback, Gf2nStrategy::Simd];
+
+    /// Name of the strategy, as returned by the functions of this module.
+    pub fn name(self) -> &'static str {
+        match self {
+            Gf2nStrategy::Fallback => "fallback",
+            Gf2nStrategy::Simd => "simd",
+        }
+    }
+
+    /// Returns whether the current CPU supports this strategy.
+    pub fn is_available(self) -> bool {
+        match self {
+            Gf2nStrategy::Fallback => true,
+            Gf2nStrategy::Simd => {
+                is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes")
+            }
+        }
+    }
+
+    /// Marks this strategy as unusable, so that the functions of this module avoid it.
+    pub fn disable(self) {
+        DISABLED.disable(self as usize);
+    }
+
+    /// Returns whether this strategy was disabled.
+    pub fn is_disabled(self) -> bool {
+        DISABLED.is_disabled(self as usize)
+    }
 }
 
-pub fn gf64_invert(data: &mut [u64; 1]) {
-    let data_ptr: *mut u64 = data.as_mut_ptr();
+dispatch! {
+    pub fn gf256_shamir_split_10(secret: &[u8; 32], output: &mut [u8; 640]) -> &'static str {
+        aarch64["neon", "aes"] if !Gf2nStrategy::Simd.is_disabled()
+            => unsafe |secret: &[u8; 32], output: &mut [u8; 640]| {
+                unsafe { gf256_shamir_split_10_simd(secret, output.as_mut_ptr()) };
+                Gf2nStrategy::Simd.name()
+            },
+        _ => |secret: &[u8; 32], output: &mut [u8; 640]| {
+            unsafe { gf256_shamir_split_10_fallback(secret, output.as_mut_ptr()) };
+            Gf2nStrategy::Fallback.name()
+        },
+    }
+}
 
-    if is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes") {
-        unsafe { gf64_invert_simd(data_ptr) }
-    } else {
-        unsafe { gf64_invert_fallback(data_ptr) }
-    };
+dispatch! {
+    pub fn gf64_invert(data: &mut [u64; 1]) -> &'static str {
+        aarch64["neon", "aes"] if !Gf2nStrategy::Simd.is_disabled()
+            => unsafe |data: &mut [u64; 1]| {
+                unsafe { gf64_invert_simd(data.as_mut_ptr()) };
+                Gf2nStrategy::Simd.name()
+            },
+        _ => |data: &mut [u64; 1]| {
+            unsafe { gf64_invert_fallback(data.as_mut_ptr()) };
+            Gf2nStrategy::Fallback.name()
+        },
+    }
 }
 
-pub fn gf128_invert(data: &mut [u64; 2]) {
-    let data_ptr: *mut u64 = data.as_mut_ptr();
+dispatch! {
+    pub fn gf128_invert(data: &mut [u64; 2]) -> &'static str {
+        aarch64["neon", "aes"] if !Gf2nStrategy::Simd.is_disabled()
+            => unsafe |data: &mut [u64; 2]| {
+                unsafe { gf128_invert_simd(data.as_mut_ptr()) };
+                Gf2nStrategy::Simd.name()
+            },
+        _ => |data: &mut [u64; 2]| {
+            unsafe { gf128_invert_fallback(data.as_mut_ptr()) };
+            Gf2nStrategy::Fallback.name()
+        },
+    }
+}
 
-    if is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes") {
-        unsafe { gf128_invert_simd(data_ptr) }
-    } else {
-        unsafe { gf128_invert_fallback(data_ptr) }
+dispatch! {
+    pub fn gf256_invert(data: &mut [u64; 4]) -> &'static str {
+        aarch64["neon", "aes"] if !Gf2nStrategy::Simd.is_disabled()
+            => unsafe |data: &mut [u64; 4]| {
+                unsafe { gf256_invert_simd(data.as_mut_ptr()) };
+                Gf2nStrategy::Simd.name()
+            },
+        _ => |data: &mut [u64; 4]| {
+            unsafe { gf256_invert_fallback(data.as_mut_ptr()) };
+            Gf2nStrategy::Fallback.name()
+        },
+    }
+}
+
+/// Inverts an element of GF(2^64) with the given strategy, if the current CPU supports it.
+pub fn gf64_invert_with(strategy: Gf2nStrategy, data: &mut [u64; 1]) -> Result<(), Unsupported> {
+    if !strategy.is_available() {
+        return Err(Unsupported(strategy));
+    }
+    let data_ptr: *mut u64 = data.as_mut_ptr();
+    match strategy {
+        Gf2nStrategy::Fallback => unsafe { gf64_invert_fallback(data_ptr) },
+        Gf2nStrategy::Simd => unsafe { gf64_invert_simd(data_ptr) },
     };
+    Ok(())
 }
 
-pub fn gf256_invert(data: &mut [u64; 4]) {
+/// Inverts an element of GF(2^128) with the given strategy, if the current CPU supports it.
+pub fn gf128_invert_with(strategy: Gf2nStrategy, data: &mut [u64; 2]) -> Result<(), Unsupported> {
+    if !strategy.is_available() {
+        return Err(Unsupported(strategy));
+    }
     let data_ptr: *mut u64 = data.as_mut_ptr();
+    match strategy {
+        Gf2nStrategy::Fallback => unsafe { gf128_invert_fallback(data_ptr) },
+        Gf2nStrategy::Simd => unsafe { gf128_invert_simd(data_ptr) },
+    };
+    Ok(())
+}
 
-    if is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes") {
-        unsafe { gf256_invert_simd(data_ptr) }
-    } else {
-        unsafe { gf256_invert_fallback(data_ptr) }
+/// Inverts an element of GF(2^256) with the given strategy, if the current CPU supports it.
+pub fn gf256_invert_with(strategy: Gf2nStrategy, data: &mut [u64; 4]) -> Result<(), Unsupported> {
+    if !strategy.is_available() {
+        return Err(Unsupported(strategy));
+    }
+    let data_ptr: *mut u64 = data.as_mut_ptr();
+    match strategy {
+        Gf2nStrategy::Fallback => unsafe { gf256_invert_fallback(data_ptr) },
+        Gf2nStrategy::Simd => unsafe { gf256_invert_simd(data_ptr) },
     };
+    Ok(())
 }
 
 #[link(name = "fallback")]
@@ -63,6 +168,23 @@ pub mod tests {
     use std::hint::black_box;
     use test::Bencher;
 
+    #[test]
+    fn test_invert_with() {
+        for strategy in Gf2nStrategy::ALL {
+            if !strategy.is_available() {
+                assert_eq!(
+                    gf64_invert_with(strategy, &mut [1]),
+                    Err(Unsupported(strategy))
+                );
+                continue;
+            }
+            let mut data = [1, 2];
+            gf128_invert_with(strategy, &mut data).unwrap();
+            gf128_invert_with(strategy, &mut data).unwrap();
+            assert_eq!(data, [1, 2]);
+        }
+    }
+
     #[bench]
     fn bench_gf256_shamir_split_10(b: &mut Bencher) {
         let data = [1; 32];
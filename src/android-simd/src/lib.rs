@@ -1,4 +1,4 @@
-#![cfg_attr(test, feature(never_type, test))]
+#![cfg_attr(test, feature(test))]
 #![cfg_attr(
     any(
         target_arch = "arm",
@@ -7,41 +7,212 @@
     feature(stdsimd)
 )]
 #![feature(let_chains, slice_as_chunks)]
+#![cfg_attr(target_arch = "arm", feature(arm_target_feature))]
+#![cfg_attr(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature(avx512_target_feature)
+)]
 
-#[cfg(all(feature = "relink", target_arch = "aarch64"))]
+#[cfg(all(
+    feature = "relink",
+    any(target_arch = "aarch64", target_arch = "x86_64")
+))]
+mod aead;
+#[cfg(all(
+    feature = "relink",
+    any(target_arch = "aarch64", target_arch = "x86_64")
+))]
 mod aes;
 mod cpu;
+mod crc;
+mod dispatch;
 #[cfg(all(feature = "relink", target_arch = "aarch64"))]
 mod gf2n;
-mod logger;
+pub mod logger;
 mod pmul;
+mod pmul_batch;
+mod polyval;
+mod selftest;
+#[cfg(test)]
+mod testutil;
 
 #[cfg(test)]
 extern crate test;
 
 use cpu::{get_arch_name, print_cpu_features};
-use logger::Logger;
-use pmul::{pmul_strategy, pmul_strategy_cheat, pmul_strategy_nosimd};
+use crc::{crc_strategy, CrcAlgorithm};
+use logger::{Level, Logger, LoggerExt};
+use pmul::{
+    available_strategies, pmul128_strategy, pmul256_strategy, pmul_strategy, pmul_strategy_cheat,
+    pmul_strategy_nosimd, pmul_with,
+};
+use pmul_batch::{pmul_batch, pmul_batch_accumulate};
+use polyval::{Ghash, Polyval};
+use selftest::run_self_tests;
+#[cfg(all(
+    feature = "relink",
+    any(target_arch = "aarch64", target_arch = "x86_64")
+))]
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "android")]
 #[allow(non_snake_case)]
 pub mod android {
     use super::hello;
-    use crate::logger::AndroidLogger;
-    use jni::objects::JClass;
+    use crate::logger::facade::{self, LogFilter};
+    use crate::logger::{
+        install_panic_hook, redirect_stdio, take_crash_record, AndroidLogger, FileLogger,
+        JavaVmLogger, LiblogLogger, Logger, LoggerExt, MultiLogger,
+    };
+    use jni::objects::{JClass, JString};
     use jni::sys::jstring;
     use jni::JNIEnv;
+    use std::sync::{Arc, Mutex};
+
+    /// File loggers created by `nativeInitLogging()`, shared with the logger of `nativeRun()`.
+    static FILE_LOGGERS: Mutex<Vec<Arc<FileLogger>>> = Mutex::new(Vec::new());
+
+    /// Routes the records of the `log` crate to logcat and to rotating files in the given
+    /// directory, filtered by the given directives (e.g. `"info,simd=debug"`). Records are written
+    /// both as text to `simd.log` and as JSON lines to `simd.jsonl`, for log ingestion.
+    #[no_mangle]
+    pub unsafe extern "C" fn Java_com_example_myrustapplication_NativeLibrary_nativeInitLogging(
+        env: JNIEnv,
+        _: JClass,
+        filters: JString,
+        log_dir: JString,
+    ) {
+        let filters: String = env
+            .get_string(filters)
+            .expect("Couldn't get Java string!")
+            .into();
+        let log_dir: String = env
+            .get_string(log_dir)
+            .expect("Couldn't get Java string!")
+            .into();
+        let filter = LogFilter::parse(&filters).expect("Invalid log filters");
+
+        let mut logger: MultiLogger<dyn Logger + Send + Sync> = MultiLogger::new().with(Box::new(
+            LiblogLogger::new("MyRustSimdApplication").expect("Couldn't create logger object"),
+        ));
+        let mut file_loggers = Vec::new();
+        let mut errors = Vec::new();
+        for file_logger in [
+            FileLogger::new(&log_dir, "simd"),
+            FileLogger::new_json_lines(&log_dir, "simd"),
+        ] {
+            match file_logger {
+                Ok(file_logger) => {
+                    let file_logger = Arc::new(file_logger);
+                    logger.push(Box::new(file_logger.clone()));
+                    file_loggers.push(file_logger);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        if facade::init(logger, filter).is_err() {
+            log::warn!("The global logger was already initialized");
+            return;
+        }
+        for e in errors {
+            log::warn!("Couldn't create the log files in {log_dir}: {e:?}");
+        }
+        *FILE_LOGGERS.lock().unwrap() = file_loggers;
+    }
+
+    /// Reports panics to logcat and to a crash record at the given path. Returns the crash record
+    /// left by the previous run, or null if there was none.
+    #[no_mangle]
+    pub unsafe extern "C" fn Java_com_example_myrustapplication_NativeLibrary_nativeInstallPanicHook(
+        env: JNIEnv,
+        _: JClass,
+        crash_record_path: JString,
+    ) -> jstring {
+        let path: String = env
+            .get_string(crash_record_path)
+            .expect("Couldn't get Java string!")
+            .into();
+        let previous_record = match take_crash_record(&path) {
+            Ok(record) => record,
+            Err(e) => {
+                log::warn!("Couldn't read the previous crash record: {e:?}");
+                None
+            }
+        };
+
+        let logger =
+            LiblogLogger::new("MyRustSimdApplication").expect("Couldn't create logger object");
+        if let Err(e) = install_panic_hook(logger, Some(path.into())) {
+            log::warn!("Couldn't install the panic hook: {e:?}");
+        }
+
+        match previous_record {
+            Some(record) => env
+                .new_string(record)
+                .expect("Couldn't create Java string!")
+                .into_raw(),
+            None => std::ptr::null_mut(),
+        }
+    }
+
+    /// Redirects Rust's stdout and stderr to logcat, for the lifetime of the process.
+    #[no_mangle]
+    pub unsafe extern "C" fn Java_com_example_myrustapplication_NativeLibrary_nativeRedirectStdio(
+        _env: JNIEnv,
+        _: JClass,
+    ) {
+        let logger =
+            LiblogLogger::new("MyRustSimdApplication").expect("Couldn't create logger object");
+        match redirect_stdio(logger) {
+            Ok(redirect) => std::mem::forget(redirect),
+            Err(e) => log::warn!("Couldn't redirect stdout and stderr: {e:?}"),
+        }
+    }
 
     #[no_mangle]
     pub unsafe extern "C" fn Java_com_example_myrustapplication_NativeLibrary_nativeRun(
         env: JNIEnv,
         _: JClass,
     ) -> jstring {
-        println!("Hello from Rust's stdout. This message is sent to /dev/null by Android.");
+        println!(
+            "Hello from Rust's stdout. This message is sent to /dev/null by Android, unless \
+             nativeRedirectStdio() was called."
+        );
 
-        let logger = AndroidLogger::new(env, "MyRustSimdApplication")
+        let android_logger = AndroidLogger::new(env, "MyRustSimdApplication")
             .expect("Couldn't create logger object");
-        let your_arch = hello(&logger);
+        let vm_logger = Arc::new(
+            JavaVmLogger::new(env, "MyRustSimdApplication").expect("Couldn't create logger object"),
+        );
+        let file_loggers = FILE_LOGGERS.lock().unwrap().clone();
+
+        // The work runs on a background thread, as it would in a thread pool, which can't log
+        // through the JNI environment of this thread.
+        let worker = std::thread::Builder::new()
+            .name("simd-worker".to_owned())
+            .spawn(move || {
+                // Stay attached to the Java VM while logging, instead of once per message.
+                let _guard = vm_logger
+                    .attach_current_thread()
+                    .expect("Couldn't attach the thread to the Java VM");
+                let mut logger = MultiLogger::<dyn Logger>::new().with(Box::new(vm_logger.clone()));
+                for file_logger in file_loggers {
+                    logger.push(Box::new(file_logger));
+                }
+                hello(&logger)
+            })
+            .expect("Couldn't spawn the worker thread");
+        let your_arch = match worker.join() {
+            Ok(your_arch) => your_arch,
+            Err(_) => {
+                // The panic hook, if installed, has already reported the panic itself.
+                android_logger
+                    .e("The worker thread panicked")
+                    .expect("Failed to log");
+                "The worker thread panicked".to_owned()
+            }
+        };
 
         let output = env
             .new_string(&your_arch)
@@ -53,103 +224,500 @@ pub mod android {
 #[cfg(test)]
 pub mod tests {
     use super::hello;
-    use crate::logger::PrintlnLogger;
+    use crate::cpu::get_arch_name;
+    use crate::logger::{CaptureLogger, Level};
 
     #[test]
     fn test() {
-        let logger = PrintlnLogger {};
-        hello(&logger);
-        // Hack to have the test's stdout be displayed.
-        assert!(false);
+        let logger = CaptureLogger::new();
+        let your_arch = hello(&logger);
+        assert_eq!(
+            your_arch,
+            format!("Your CPU architecture is {}", get_arch_name())
+        );
+
+        let messages = logger.messages();
+        assert_eq!(
+            messages[..2],
+            [
+                (Level::Info, "Hello Rust world".to_owned()),
+                (Level::Info, your_arch),
+            ]
+        );
+        let records = logger.with_field(Level::Info, "arch", get_arch_name());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "Detected CPU features");
+        assert!(records[0].field("enabled").is_some());
+        assert!(logger.contains(
+            Level::Info,
+            "Testing polynomial multiplication instructions"
+        ));
+
+        let records = logger.with_field(Level::Debug, "operation", "pmul_nosimd");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "Computed operation");
+        assert_eq!(records[0].field("strategy"), Some("nosimd"));
+        assert_eq!(
+            records[0].field("input"),
+            Some("[1234567890abcdef, fedcba0987654321]")
+        );
+        assert_eq!(
+            records[0].field("output"),
+            Some("0e038d8eab3af47a1f31f87ebb8c810f")
+        );
+        assert!(records[0].field("duration").is_some());
+
+        // The self-tests also report their results for the "pmul" operation.
+        assert!(logger.contains(Level::Info, "Running self-tests"));
+        assert!(!logger.contains(Level::Error, "Self-test failed"));
+        assert!(!logger.contains(Level::Error, "Round trip failed"));
+        let records = logger
+            .with_field(Level::Debug, "operation", "pmul")
+            .into_iter()
+            .filter(|record| record.message == "Computed operation")
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].field("output"),
+            Some("0e038d8eab3af47a1f31f87ebb8c810f")
+        );
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(records[0].field("strategy"), Some("aarch64_neon"));
+
+        let records = logger.with_field(Level::Debug, "operation", "pmul_with");
+        assert!(records
+            .iter()
+            .any(|record| record.field("strategy") == Some("nosimd")));
+        for record in &records {
+            assert_eq!(
+                record.field("output"),
+                Some("0e038d8eab3af47a1f31f87ebb8c810f")
+            );
+        }
+
+        #[cfg(all(
+            feature = "relink",
+            any(target_arch = "aarch64", target_arch = "x86_64")
+        ))]
+        {
+            let records = logger
+                .with_field(Level::Debug, "operation", "aesenc")
+                .into_iter()
+                .filter(|record| record.message == "Computed operation")
+                .collect::<Vec<_>>();
+            assert_eq!(records.len(), 1);
+            assert_eq!(
+                records[0].field("output"),
+                Some(format!("{:02x?}", [0x7e; 16]).as_str())
+            );
+        }
+    }
+
+    #[cfg(all(
+        feature = "relink",
+        any(target_arch = "aarch64", target_arch = "x86_64")
+    ))]
+    #[test]
+    fn test_check_round_trip() {
+        let logger = CaptureLogger::new();
+        super::check_round_trip(&logger, "op", "nosimd", b"ok", &Ok::<_, ()>(b"ok".to_vec()));
+        assert!(logger.records().is_empty());
+
+        super::check_round_trip(&logger, "op", "nosimd", b"ok", &Err(()));
+        let records = logger.with_field(Level::Error, "operation", "op");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "Round trip failed");
+        assert_eq!(records[0].field("expected"), Some("[6f, 6b]"));
+        assert_eq!(records[0].field("output"), Some("Err(())"));
     }
 }
 
-pub fn hello<L: Logger>(logger: &L) -> String {
+pub fn hello(logger: &dyn Logger) -> String {
     let your_arch = format!("Your CPU architecture is {}", get_arch_name());
 
-    logger.d("Hello Rust world").expect("Failed to log");
-    logger.d(&your_arch).expect("Failed to log");
+    logger.i("Hello Rust world").expect("Failed to log");
+    logger.i(&your_arch).expect("Failed to log");
 
     print_cpu_features(logger).expect("Failed to log");
 
+    // Disable the broken strategies before running anything else.
+    logger.i("Running self-tests").expect("Failed to log");
+    let failures = run_self_tests(logger);
+    let mut result = your_arch;
+    for failure in &failures {
+        result.push_str(&format!("\nSelf-test failed: {failure}"));
+    }
+
     logger
-        .d("Testing polynomial multiplication instructions")
+        .i("Testing polynomial multiplication instructions")
         .expect("Failed to log");
     let a: u64 = 0x1234567890abcdef;
     let b: u64 = 0xfedcba0987654321;
 
-    let (product, strategy) = pmul_strategy_nosimd(a, b);
-    logger
-        .d(format!(
-            "pmul_nosimd({a:016x?}, {b:016x?}) = {product:032x?} [strategy = {strategy}]"
-        ))
-        .expect("Failed to log");
+    for (operation, pmul) in [
+        (
+            "pmul_nosimd",
+            pmul_strategy_nosimd as fn(u64, u64) -> (u128, &'static str),
+        ),
+        ("pmul_cheat", pmul_strategy_cheat),
+        ("pmul", pmul_strategy),
+    ] {
+        let start = Instant::now();
+        let (product, strategy) = pmul(a, b);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            operation,
+            strategy,
+            format!("{:016x?}", [a, b]),
+            format!("{product:032x}"),
+            duration,
+        );
+    }
 
-    let (product, strategy) = pmul_strategy_cheat(a, b);
-    logger
-        .d(format!(
-            "pmul_cheat({a:016x?}, {b:016x?}) = {product:032x?} [strategy = {strategy}]"
-        ))
-        .expect("Failed to log");
+    let (a128, b128) = ((a as u128) << 64 | b as u128, (b as u128) << 64 | a as u128);
+    let start = Instant::now();
+    let (product, strategy) = pmul128_strategy(a128, b128);
+    let duration = start.elapsed();
+    log_operation(
+        logger,
+        "pmul128",
+        strategy,
+        format!("{:032x?}", [a128, b128]),
+        format!("{product:032x?}"),
+        duration,
+    );
 
-    let (product, strategy) = pmul_strategy(a, b);
-    logger
-        .d(format!(
-            "pmul({a:016x?}, {b:016x?}) = {product:032x?} [strategy = {strategy}]"
-        ))
-        .expect("Failed to log");
+    let start = Instant::now();
+    let (product, strategy) = pmul256_strategy([a128, b128], [b128, a128]);
+    let duration = start.elapsed();
+    log_operation(
+        logger,
+        "pmul256",
+        strategy,
+        format!("{:032x?}", [[a128, b128], [b128, a128]]),
+        format!("{product:032x?}"),
+        duration,
+    );
 
-    #[cfg(all(feature = "relink", target_arch = "aarch64"))]
+    let mut products = [0; 2];
+    let start = Instant::now();
+    let strategy = pmul_batch(&[a, b], &[b, a], &mut products);
+    let duration = start.elapsed();
+    log_operation(
+        logger,
+        "pmul_batch",
+        strategy,
+        format!("{:016x?}", [[a, b], [b, a]]),
+        format!("{products:032x?}"),
+        duration,
+    );
+
+    let start = Instant::now();
+    let strategy = pmul_batch_accumulate(&[a, b], &[a, b], &mut products);
+    let duration = start.elapsed();
+    log_operation(
+        logger,
+        "pmul_batch_accumulate",
+        strategy,
+        format!("{:016x?}", [[a, b], [a, b]]),
+        format!("{products:032x?}"),
+        duration,
+    );
+
+    for strategy in available_strategies() {
+        let start = Instant::now();
+        let product = pmul_with(strategy, a, b).expect("Available strategy is unsupported");
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            "pmul_with",
+            strategy.name(),
+            format!("{:016x?}", [a, b]),
+            format!("{product:032x}"),
+            duration,
+        );
+    }
+
+    logger.i("Testing universal hashes").expect("Failed to log");
+    let (key, data) = (a128.to_le_bytes(), b128.to_le_bytes());
+
+    let start = Instant::now();
+    let mut ghash = Ghash::new(&key);
+    ghash.update_padded(&data);
+    let strategy = ghash.strategy();
+    let hash = ghash.finalize();
+    let duration = start.elapsed();
+    log_operation(
+        logger,
+        "ghash",
+        strategy.name(),
+        format!("{:02x?}", [key, data]),
+        format!("{hash:02x?}"),
+        duration,
+    );
+
+    let start = Instant::now();
+    let mut polyval = Polyval::new(&key);
+    polyval.update_padded(&data);
+    let strategy = polyval.strategy();
+    let hash = polyval.finalize();
+    let duration = start.elapsed();
+    log_operation(
+        logger,
+        "polyval",
+        strategy.name(),
+        format!("{:02x?}", [key, data]),
+        format!("{hash:02x?}"),
+        duration,
+    );
+
+    logger.i("Testing CRCs").expect("Failed to log");
+    let data = [key, data].concat();
+    for algorithm in CrcAlgorithm::ALL {
+        let start = Instant::now();
+        let (crc, strategy) = crc_strategy(algorithm, &data);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            algorithm.name(),
+            strategy,
+            format!("{data:02x?}"),
+            format!("{crc:016x}"),
+            duration,
+        );
+    }
+
+    #[cfg(all(
+        feature = "relink",
+        any(target_arch = "aarch64", target_arch = "x86_64")
+    ))]
     {
         logger
-            .d("Testing aesenc implementation")
+            .i("Testing aesenc implementation")
             .expect("Failed to log");
         let src = [1; 16];
         let key = [2; 16];
         let mut dst = src;
+        let start = Instant::now();
         let strategy = aes::aesenc(&mut dst, &key);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            "aesenc",
+            strategy,
+            format!("{:02x?}", [src, key]),
+            format!("{dst:02x?}"),
+            duration,
+        );
+
         logger
-            .d(format!(
-                "aesenc({src:02x?}, {key:02x?}) = {dst:02x?} [strategy = {strategy}]"
-            ))
+            .i("Testing AES implementation")
             .expect("Failed to log");
+        for key_len in [16, 24, 32] {
+            let key = (0..key_len as u8).collect::<Vec<_>>();
+            let src = [0x42; aes::BLOCK_LEN];
+            let mut dst = src;
+            let start = Instant::now();
+            let cipher = aes::Aes::new(&key);
+            cipher.encrypt_block(&mut dst);
+            let duration = start.elapsed();
+            log_operation(
+                logger,
+                &format!("aes{}_encrypt", key_len * 8),
+                cipher.strategy().name(),
+                format!("{:02x?}", [&key[..], &src]),
+                format!("{dst:02x?}"),
+                duration,
+            );
+        }
+
+        let key = [0x42; 16];
+        let src = [0x42; 3 * aes::BLOCK_LEN + 5];
+        let mut dst = src;
+        let mut counter = [0x24; aes::BLOCK_LEN];
+        let start = Instant::now();
+        let cipher = aes::Aes::new(&key);
+        cipher.apply_ctr(&mut counter, &mut dst);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            "aes128_ctr",
+            cipher.strategy().name(),
+            format!("{:02x?}", [&key[..], &src]),
+            format!("{dst:02x?}"),
+            duration,
+        );
 
         logger
-            .d("Testing GF(2^n) implementation")
+            .i("Testing AES-GCM and AES-GCM-SIV implementations")
             .expect("Failed to log");
+        let key = [0x42; 16];
+        let nonce = [0x24; aead::NONCE_LEN];
+        let plaintext = b"Hello from Rust";
+        let start = Instant::now();
+        let gcm = aead::AesGcm::new(&key);
+        // A failure to seal shows up as a failed round trip.
+        let ciphertext = gcm.seal(&nonce, &[], plaintext).unwrap_or_default();
+        let opened = gcm.open(&nonce, &[], &ciphertext);
+        let duration = start.elapsed();
+        check_round_trip(
+            logger,
+            "aes128_gcm_open",
+            aes::preferred_strategy().name(),
+            plaintext,
+            &opened,
+        );
+        log_operation(
+            logger,
+            "aes128_gcm_seal",
+            aes::preferred_strategy().name(),
+            format!("{:02x?}", [&key[..], &nonce, plaintext]),
+            format!("{ciphertext:02x?}"),
+            duration,
+        );
+        let start = Instant::now();
+        let siv = aead::AesGcmSiv::new(&key);
+        let ciphertext = siv.seal(&nonce, &[], plaintext).unwrap_or_default();
+        let opened = siv.open(&nonce, &[], &ciphertext);
+        let duration = start.elapsed();
+        check_round_trip(
+            logger,
+            "aes128_gcm_siv_open",
+            aes::preferred_strategy().name(),
+            plaintext,
+            &opened,
+        );
+        log_operation(
+            logger,
+            "aes128_gcm_siv_seal",
+            aes::preferred_strategy().name(),
+            format!("{:02x?}", [&key[..], &nonce, plaintext]),
+            format!("{ciphertext:02x?}"),
+            duration,
+        );
+    }
 
-        let src = [1];
-        let mut dst = src;
-        gf2n::gf64_invert(&mut dst);
+    #[cfg(all(feature = "relink", target_arch = "aarch64"))]
+    {
         logger
-            .d(format!("invert({src:016x?}) = {dst:016x?}"))
+            .i("Testing GF(2^n) implementation")
             .expect("Failed to log");
 
+        let src = [1];
+        let mut dst = src;
+        let start = Instant::now();
+        let strategy = gf2n::gf64_invert(&mut dst);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            "gf64_invert",
+            strategy,
+            format!("{src:016x?}"),
+            format!("{dst:016x?}"),
+            duration,
+        );
+
         let src = [1, 2];
         let mut dst = src;
-        gf2n::gf128_invert(&mut dst);
-        logger
-            .d(format!("invert({src:016x?}) = {dst:016x?}"))
-            .expect("Failed to log");
+        let start = Instant::now();
+        let strategy = gf2n::gf128_invert(&mut dst);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            "gf128_invert",
+            strategy,
+            format!("{src:016x?}"),
+            format!("{dst:016x?}"),
+            duration,
+        );
 
         let src = [1, 2, 3, 4];
         let mut dst = src;
-        gf2n::gf256_invert(&mut dst);
-        logger
-            .d(format!("invert({src:016x?}) = {dst:016x?}"))
-            .expect("Failed to log");
+        let start = Instant::now();
+        let strategy = gf2n::gf256_invert(&mut dst);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            "gf256_invert",
+            strategy,
+            format!("{src:016x?}"),
+            format!("{dst:016x?}"),
+            duration,
+        );
 
         logger
-            .d("Testing Shamir implementation")
+            .i("Testing Shamir implementation")
             .expect("Failed to log");
 
         let src = [1; 32];
         let mut dst = [0; 640];
-        gf2n::gf256_shamir_split_10(&src, &mut dst);
-        logger
-            .d(format!("gf256_shamir_split_10({src:02x?}) = {dst:02x?}"))
-            .expect("Failed to log");
+        let start = Instant::now();
+        let strategy = gf2n::gf256_shamir_split_10(&src, &mut dst);
+        let duration = start.elapsed();
+        log_operation(
+            logger,
+            "gf256_shamir_split_10",
+            strategy,
+            format!("{src:02x?}"),
+            format!("{dst:02x?}"),
+            duration,
+        );
+    }
+
+    result
+}
+
+/// Logs an error if decrypting the output of an operation didn't give back its input.
+#[cfg(all(
+    feature = "relink",
+    any(target_arch = "aarch64", target_arch = "x86_64")
+))]
+fn check_round_trip<E: Debug>(
+    logger: &dyn Logger,
+    operation: &str,
+    strategy: &str,
+    plaintext: &[u8],
+    opened: &Result<Vec<u8>, E>,
+) {
+    if opened.as_deref().ok() == Some(plaintext) {
+        return;
     }
+    logger
+        .log_fields(
+            Level::Error,
+            "Round trip failed",
+            &[
+                ("operation", operation.into()),
+                ("strategy", strategy.into()),
+                ("expected", format!("{plaintext:02x?}").into()),
+                ("output", format!("{opened:02x?}").into()),
+            ],
+        )
+        .expect("Failed to log");
+}
 
-    your_arch
+/// Logs the result of an operation as a structured record, so that the strategy that ran on each
+/// device can be queried from the logs.
+fn log_operation(
+    logger: &dyn Logger,
+    operation: &str,
+    strategy: &str,
+    input: String,
+    output: String,
+    duration: Duration,
+) {
+    logger
+        .log_fields(
+            Level::Debug,
+            "Computed operation",
+            &[
+                ("operation", operation.into()),
+                ("strategy", strategy.into()),
+                ("input", input.into()),
+                ("output", output.into()),
+                ("duration", duration.into()),
+            ],
+        )
+        .expect("Failed to log");
 }
@@ -2,28 +2,263 @@
 use jni::objects::{JClass, JObject, JString, JValue};
 #[cfg(target_os = "android")]
 use jni::JNIEnv;
-use std::fmt::Debug;
+use std::io;
+use std::sync::Arc;
 
+mod capture;
+mod chunk;
+mod crash;
+pub mod facade;
+mod fields;
+mod file;
+#[cfg(target_os = "android")]
+mod javavm;
+mod liblog;
+mod multi;
+#[cfg(unix)]
+mod redirect;
+
+pub use capture::{CaptureLogger, CapturedRecord};
+pub use chunk::LOGCAT_MAX_MESSAGE_LEN;
+pub use crash::{install_panic_hook, take_crash_record};
+pub use fields::{Field, Value};
+pub use file::FileLogger;
+#[cfg(target_os = "android")]
+pub use javavm::JavaVmLogger;
+pub use liblog::{LiblogError, LiblogLogger, LogBuffer};
+pub use multi::MultiLogger;
+#[cfg(unix)]
+pub use redirect::{redirect_stdio, StdioRedirect};
+
+/// Severity of a log message, ordered from the least to the most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Verbose,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Single-letter abbreviation of the level, as printed by logcat.
+    fn letter(self) -> char {
+        match self {
+            Level::Verbose => 'V',
+            Level::Debug => 'D',
+            Level::Info => 'I',
+            Level::Warn => 'W',
+            Level::Error => 'E',
+        }
+    }
+
+    /// Name of the corresponding static method in the android.util.Log class.
+    #[cfg_attr(not(target_os = "android"), allow(dead_code))]
+    fn android_method(self) -> &'static str {
+        match self {
+            Level::Verbose => "v",
+            Level::Debug => "d",
+            Level::Info => "i",
+            Level::Warn => "w",
+            Level::Error => "e",
+        }
+    }
+}
+
+/// Error returned by a logger backend.
+#[derive(Debug)]
+pub enum LogError {
+    /// Error in the JNI calls to the android.util.Log class.
+    #[cfg(target_os = "android")]
+    Jni(jni::errors::Error),
+    /// Error in the calls to liblog.
+    Liblog(LiblogError),
+    /// I/O error, e.g. when writing to a file.
+    Io(io::Error),
+    /// Errors of several backends of a `MultiLogger`.
+    Multiple(Vec<LogError>),
+    /// The backend is locked, e.g. by a thread that panicked while logging. Returned by
+    /// `try_log()` instead of waiting.
+    WouldBlock,
+}
+
+#[cfg(target_os = "android")]
+impl From<jni::errors::Error> for LogError {
+    fn from(e: jni::errors::Error) -> Self {
+        LogError::Jni(e)
+    }
+}
+
+impl From<LiblogError> for LogError {
+    fn from(e: LiblogError) -> Self {
+        LogError::Liblog(e)
+    }
+}
+
+impl From<io::Error> for LogError {
+    fn from(e: io::Error) -> Self {
+        LogError::Io(e)
+    }
+}
+
+/// Logger backend. This trait is object-safe, so that backends can be selected at runtime with
+/// `&dyn Logger`. The convenience methods for each level are provided by `LoggerExt`.
 pub trait Logger {
-    type E: Debug;
+    /// Minimum level of the messages that this logger prints.
+    fn min_level(&self) -> Level;
 
-    /// Prints a message at the debug level.
-    fn d(&self, message: impl AsRef<str>) -> Result<(), Self::E>;
+    /// Maximum length in bytes of a message that this logger prints in one piece, or `None` if
+    /// there is no limit.
+    fn max_message_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Prints a message at the given level, without any filtering or splitting.
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError>;
+
+    /// Prints a message at the given level, unless it is below the minimum level. Messages longer
+    /// than the maximum length are split into several chunks.
+    fn log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        if level < self.min_level() {
+            return Ok(());
+        }
+        match self.max_message_len() {
+            Some(max_len) if message.len() > max_len => {
+                for chunk in chunk::split_message(message, max_len) {
+                    self.write(level, &chunk)?;
+                }
+                Ok(())
+            }
+            _ => self.write(level, message),
+        }
+    }
+
+    /// Like `log()`, but returns `LogError::WouldBlock` instead of waiting for a lock that the
+    /// backend holds while printing. The panic hook uses it, as the panicking thread may hold the
+    /// lock itself. Backends without a lock don't need to override it.
+    fn try_log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        self.log(level, message)
+    }
+
+    /// Prints a message with structured fields at the given level, unless it is below the minimum
+    /// level. Backends that don't record fields separately append them to the message, in the form
+    /// `message [key = value, ...]`.
+    fn log_fields(&self, level: Level, message: &str, fields: &[Field]) -> Result<(), LogError> {
+        if level < self.min_level() {
+            return Ok(());
+        }
+        self.log(level, &fields::format_text(message, fields))
+    }
 }
 
-#[cfg(test)]
-pub struct PrintlnLogger {}
+impl<L: Logger + ?Sized> Logger for &L {
+    fn min_level(&self) -> Level {
+        (**self).min_level()
+    }
 
-#[cfg(test)]
-impl Logger for PrintlnLogger {
-    type E = !;
+    fn max_message_len(&self) -> Option<usize> {
+        (**self).max_message_len()
+    }
 
-    fn d(&self, message: impl AsRef<str>) -> Result<(), Self::E> {
-        println!("{}", message.as_ref());
-        Ok(())
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).write(level, message)
+    }
+
+    fn log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).log(level, message)
+    }
+
+    fn try_log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).try_log(level, message)
+    }
+
+    fn log_fields(&self, level: Level, message: &str, fields: &[Field]) -> Result<(), LogError> {
+        (**self).log_fields(level, message, fields)
     }
 }
 
+impl<L: Logger + ?Sized> Logger for Box<L> {
+    fn min_level(&self) -> Level {
+        (**self).min_level()
+    }
+
+    fn max_message_len(&self) -> Option<usize> {
+        (**self).max_message_len()
+    }
+
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).write(level, message)
+    }
+
+    fn log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).log(level, message)
+    }
+
+    fn try_log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).try_log(level, message)
+    }
+
+    fn log_fields(&self, level: Level, message: &str, fields: &[Field]) -> Result<(), LogError> {
+        (**self).log_fields(level, message, fields)
+    }
+}
+
+impl<L: Logger + ?Sized> Logger for Arc<L> {
+    fn min_level(&self) -> Level {
+        (**self).min_level()
+    }
+
+    fn max_message_len(&self) -> Option<usize> {
+        (**self).max_message_len()
+    }
+
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).write(level, message)
+    }
+
+    fn log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).log(level, message)
+    }
+
+    fn try_log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        (**self).try_log(level, message)
+    }
+
+    fn log_fields(&self, level: Level, message: &str, fields: &[Field]) -> Result<(), LogError> {
+        (**self).log_fields(level, message, fields)
+    }
+}
+
+/// Convenience methods to print messages at each level, for any `Logger` including `dyn Logger`.
+pub trait LoggerExt: Logger {
+    /// Prints a message at the verbose level.
+    fn v(&self, message: impl AsRef<str>) -> Result<(), LogError> {
+        self.log(Level::Verbose, message.as_ref())
+    }
+
+    /// Prints a message at the debug level.
+    fn d(&self, message: impl AsRef<str>) -> Result<(), LogError> {
+        self.log(Level::Debug, message.as_ref())
+    }
+
+    /// Prints a message at the info level.
+    fn i(&self, message: impl AsRef<str>) -> Result<(), LogError> {
+        self.log(Level::Info, message.as_ref())
+    }
+
+    /// Prints a message at the warn level.
+    fn w(&self, message: impl AsRef<str>) -> Result<(), LogError> {
+        self.log(Level::Warn, message.as_ref())
+    }
+
+    /// Prints a message at the error level.
+    fn e(&self, message: impl AsRef<str>) -> Result<(), LogError> {
+        self.log(Level::Error, message.as_ref())
+    }
+}
+
+impl<L: Logger + ?Sized> LoggerExt for L {}
+
 #[cfg(target_os = "android")]
 pub struct AndroidLogger<'a> {
     /// JNI environment.
@@ -32,6 +267,8 @@ pub struct AndroidLogger<'a> {
     log_class: JClass<'a>,
     /// Tag for log messages.
     tag: JString<'a>,
+    /// Messages below this level are discarded.
+    min_level: Level,
 }
 
 #[cfg(target_os = "android")]
@@ -41,24 +278,132 @@ impl<'a> AndroidLogger<'a> {
             env,
             log_class: env.find_class("android/util/Log")?,
             tag: env.new_string(tag)?,
+            min_level: Level::Verbose,
         })
     }
+
+    /// Discards messages below the given level.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
 }
 
 #[cfg(target_os = "android")]
 impl<'a> Logger for AndroidLogger<'a> {
-    type E = jni::errors::Error;
+    fn min_level(&self) -> Level {
+        self.min_level
+    }
 
-    fn d(&self, message: impl AsRef<str>) -> Result<(), Self::E> {
+    fn max_message_len(&self) -> Option<usize> {
+        Some(LOGCAT_MAX_MESSAGE_LEN)
+    }
+
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        // Long messages are written in many chunks before control returns to Java, so each local
+        // reference is deleted explicitly to not overflow the local reference table.
+        let message = self.env.auto_local(self.env.new_string(message)?);
         self.env.call_static_method(
             self.log_class,
-            "d",
+            level.android_method(),
             "(Ljava/lang/String;Ljava/lang/String;)I",
             &[
                 JValue::Object(JObject::from(self.tag)),
-                JValue::Object(JObject::from(self.env.new_string(message)?)),
+                JValue::Object(message.as_obj()),
             ],
         )?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct VecLogger {
+        min_level: Level,
+        max_message_len: Option<usize>,
+        records: RefCell<Vec<(Level, String)>>,
+    }
+
+    impl Logger for VecLogger {
+        fn min_level(&self) -> Level {
+            self.min_level
+        }
+
+        fn max_message_len(&self) -> Option<usize> {
+            self.max_message_len
+        }
+
+        fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+            self.records.borrow_mut().push((level, message.to_owned()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_min_level() {
+        let logger = VecLogger {
+            min_level: Level::Info,
+            max_message_len: None,
+            records: RefCell::new(Vec::new()),
+        };
+        logger.v("verbose").unwrap();
+        logger.d("debug").unwrap();
+        logger.i("info").unwrap();
+        logger.w("warn").unwrap();
+        logger.e("error").unwrap();
+
+        assert_eq!(
+            *logger.records.borrow(),
+            [
+                (Level::Info, "info".to_owned()),
+                (Level::Warn, "warn".to_owned()),
+                (Level::Error, "error".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_message_len() {
+        let logger = VecLogger {
+            min_level: Level::Verbose,
+            max_message_len: Some(17),
+            records: RefCell::new(Vec::new()),
+        };
+        logger.d("short message").unwrap();
+        logger.w("first line\nsecond line").unwrap();
+
+        assert_eq!(
+            *logger.records.borrow(),
+            [
+                (Level::Debug, "short message".to_owned()),
+                (Level::Warn, "[1/2] first line".to_owned()),
+                (Level::Warn, "[2/2] second line".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_fields() {
+        let logger = VecLogger {
+            min_level: Level::Debug,
+            max_message_len: None,
+            records: RefCell::new(Vec::new()),
+        };
+        let fields = [("operation", "pmul".into()), ("count", 2u64.into())];
+        logger
+            .log_fields(Level::Verbose, "verbose", &fields)
+            .unwrap();
+        logger.log_fields(Level::Debug, "debug", &fields).unwrap();
+
+        assert_eq!(
+            *logger.records.borrow(),
+            [(
+                Level::Debug,
+                "debug [operation = pmul, count = 2]".to_owned()
+            )]
+        );
+    }
+}
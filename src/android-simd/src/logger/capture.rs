@@ -0,0 +1,202 @@
+//! Logger backend that keeps the records in memory, so that they can be inspected later.
+
+use super::{Field, Level, LogError, Logger};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::SystemTime;
+
+/// A message printed to a `CaptureLogger`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedRecord {
+    pub level: Level,
+    pub message: String,
+    /// Structured fields, with their values formatted as text.
+    pub fields: Vec<(String, String)>,
+    pub timestamp: SystemTime,
+}
+
+impl CapturedRecord {
+    /// Returns the value of the given field, if the record has it.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Logger that stores all the records in memory.
+pub struct CaptureLogger {
+    /// Messages below this level are discarded.
+    min_level: Level,
+    /// Records captured so far.
+    records: Mutex<Vec<CapturedRecord>>,
+}
+
+impl CaptureLogger {
+    pub fn new() -> Self {
+        Self {
+            min_level: Level::Verbose,
+            records: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Discards messages below the given level.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Returns the records captured so far.
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.lock().clone()
+    }
+
+    /// Returns the levels and messages of the records captured so far.
+    pub fn messages(&self) -> Vec<(Level, String)> {
+        self.lock()
+            .iter()
+            .map(|record| (record.level, record.message.clone()))
+            .collect()
+    }
+
+    /// Returns whether a record at the given level contains the given text.
+    pub fn contains(&self, level: Level, text: &str) -> bool {
+        self.lock()
+            .iter()
+            .any(|record| record.level == level && record.message.contains(text))
+    }
+
+    /// Returns the records at the given level that have the given value for a field.
+    pub fn with_field(&self, level: Level, key: &str, value: &str) -> Vec<CapturedRecord> {
+        self.lock()
+            .iter()
+            .filter(|record| record.level == level && record.field(key) == Some(value))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes all the records captured so far.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn push(&self, level: Level, message: &str, fields: &[Field]) {
+        self.lock().push(Self::record(level, message, fields));
+    }
+
+    fn record(level: Level, message: &str, fields: &[Field]) -> CapturedRecord {
+        CapturedRecord {
+            level,
+            message: message.to_owned(),
+            fields: fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<CapturedRecord>> {
+        // The records remain consistent even if another thread panicked while holding the lock.
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn try_lock(&self) -> Result<MutexGuard<'_, Vec<CapturedRecord>>, LogError> {
+        match self.records.try_lock() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(e)) => Ok(e.into_inner()),
+            Err(TryLockError::WouldBlock) => Err(LogError::WouldBlock),
+        }
+    }
+}
+
+impl Default for CaptureLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Logger for CaptureLogger {
+    fn min_level(&self) -> Level {
+        self.min_level
+    }
+
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        self.push(level, message, &[]);
+        Ok(())
+    }
+
+    fn try_log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        if level >= self.min_level {
+            self.try_lock()?.push(Self::record(level, message, &[]));
+        }
+        Ok(())
+    }
+
+    /// Keeps the fields separate from the message.
+    fn log_fields(&self, level: Level, message: &str, fields: &[Field]) -> Result<(), LogError> {
+        if level >= self.min_level {
+            self.push(level, message, fields);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::LoggerExt;
+
+    #[test]
+    fn test_capture_logger() {
+        let logger = CaptureLogger::new().with_min_level(Level::Debug);
+        let start = SystemTime::now();
+        logger.v("verbose").unwrap();
+        logger.d("debug").unwrap();
+        logger.e("error").unwrap();
+
+        assert_eq!(
+            logger.messages(),
+            [
+                (Level::Debug, "debug".to_owned()),
+                (Level::Error, "error".to_owned()),
+            ]
+        );
+        assert!(logger.contains(Level::Error, "err"));
+        assert!(!logger.contains(Level::Debug, "err"));
+
+        let records = logger.records();
+        assert!(records[0].timestamp >= start);
+
+        logger
+            .log_fields(Level::Info, "fields", &[("strategy", "nosimd".into())])
+            .unwrap();
+        let records = logger.with_field(Level::Info, "strategy", "nosimd");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "fields");
+        assert_eq!(
+            records[0].fields,
+            [("strategy".to_owned(), "nosimd".to_owned())]
+        );
+        assert!(logger
+            .with_field(Level::Info, "strategy", "other")
+            .is_empty());
+
+        logger.clear();
+        assert!(logger.records().is_empty());
+    }
+
+    #[test]
+    fn test_capture_logger_try_log() {
+        let logger = CaptureLogger::new().with_min_level(Level::Info);
+        logger.try_log(Level::Debug, "debug").unwrap();
+        logger.try_log(Level::Info, "unlocked").unwrap();
+        let guard = logger.lock();
+        assert!(matches!(
+            logger.try_log(Level::Info, "locked"),
+            Err(LogError::WouldBlock)
+        ));
+        drop(guard);
+        assert_eq!(logger.messages(), [(Level::Info, "unlocked".to_owned())]);
+    }
+}
@@ -0,0 +1,142 @@
+//! Splitting of long messages into chunks that fit in a single logcat entry.
+
+/// Maximum length in bytes of a message that logcat prints without truncating it. Logcat entries
+/// are limited to 4068 bytes, including the priority, the tag and the null terminators.
+pub const LOGCAT_MAX_MESSAGE_LEN: usize = 4000;
+
+/// Splits a message into chunks of at most `max_len` bytes, each one prefixed by a continuation
+/// marker such as `[2/5] `. Messages that already fit are returned as is.
+///
+/// Chunks are cut at line boundaries if possible, otherwise at character boundaries.
+pub fn split_message(message: &str, max_len: usize) -> Vec<String> {
+    if message.len() <= max_len {
+        return vec![message.to_owned()];
+    }
+
+    // The space reserved for the markers depends on the number of chunks, so we retry with a
+    // larger reservation until the number of digits is sufficient.
+    let mut digits = 1;
+    loop {
+        // The marker is "[i/n] ", with i and n having at most the given number of digits.
+        let marker_len = 2 * digits + 4;
+        assert!(
+            max_len >= marker_len + 4,
+            "The maximum message length is too small to fit a continuation marker"
+        );
+
+        let chunks = split_chunks(message, max_len - marker_len);
+        let count = chunks.len();
+        if count.to_string().len() <= digits {
+            return chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| format!("[{}/{count}] {chunk}", i + 1))
+                .collect();
+        }
+        digits += 1;
+    }
+}
+
+/// Splits a string into slices of at most `max_len` bytes, which must be at least the size of one
+/// UTF-8 character (4 bytes). Newlines at which the string is cut are dropped.
+fn split_chunks(mut rest: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    while rest.len() > max_len {
+        // A newline right after max_len bytes is also a valid cut.
+        match rest.as_bytes()[..=max_len]
+            .iter()
+            .rposition(|&b| b == b'\n')
+        {
+            Some(newline) if newline != 0 => {
+                chunks.push(&rest[..newline]);
+                rest = &rest[newline + 1..];
+            }
+            _ => {
+                let mut end = max_len;
+                while !rest.is_char_boundary(end) {
+                    end -= 1;
+                }
+                chunks.push(&rest[..end]);
+                rest = &rest[end..];
+            }
+        }
+    }
+    chunks.push(rest);
+    chunks
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message_short() {
+        assert_eq!(split_message("Hello\nworld", 11), ["Hello\nworld"]);
+    }
+
+    #[test]
+    fn test_split_message_lines() {
+        assert_eq!(
+            split_message("aaaa\nbbbb\ncccc\ndddd", 16),
+            ["[1/2] aaaa\nbbbb", "[2/2] cccc\ndddd"]
+        );
+        assert_eq!(
+            split_message("aaaa\nbbbb\ncccc\ndddd", 10),
+            ["[1/4] aaaa", "[2/4] bbbb", "[3/4] cccc", "[4/4] dddd"]
+        );
+    }
+
+    #[test]
+    fn test_split_message_long_line() {
+        assert_eq!(
+            split_message("aaaaaaaaaabbb\nccc", 16),
+            ["[1/2] aaaaaaaaaa", "[2/2] bbb\nccc"]
+        );
+    }
+
+    #[test]
+    fn test_split_message_utf8() {
+        // Each of these characters is 3 bytes long.
+        let message = "€€€€€€€€";
+        let chunks = split_message(message, 14);
+        assert_eq!(chunks, ["[1/4] €€", "[2/4] €€", "[3/4] €€", "[4/4] €€"]);
+        for chunk in chunks {
+            assert!(chunk.len() <= 14);
+        }
+
+        let chunks = split_message(message, 15);
+        assert_eq!(chunks, ["[1/3] €€€", "[2/3] €€€", "[3/3] €€"]);
+    }
+
+    #[test]
+    fn test_split_message_many_chunks() {
+        // With one-digit markers, there would be more than 9 chunks.
+        let message = "x\n".repeat(50);
+        let chunks = split_message(&message, 12);
+        assert_eq!(chunks.len(), 25);
+        assert_eq!(chunks[0], "[1/25] x\nx");
+        assert_eq!(chunks[24], "[25/25] x\nx\n");
+        for chunk in chunks {
+            assert!(chunk.len() <= 12);
+        }
+    }
+
+    #[test]
+    fn test_split_message_cpuinfo() {
+        let message = "processor\t: 0\nFeatures\t: fp asimd evtstrm aes pmull sha1 sha2 crc32\n\n"
+            .repeat(200);
+        let chunks = split_message(&message, LOGCAT_MAX_MESSAGE_LEN);
+        assert_eq!(chunks.len(), 4);
+        for chunk in &chunks {
+            assert!(chunk.len() <= LOGCAT_MAX_MESSAGE_LEN);
+        }
+
+        // Only the markers and the newlines at the cuts are missing.
+        let joined = chunks
+            .iter()
+            .map(|chunk| chunk.split_once("] ").unwrap().1)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(joined, message);
+    }
+}
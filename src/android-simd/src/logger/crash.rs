@@ -0,0 +1,219 @@
+//! Panic hook reporting panics to a logger and to a crash record file.
+
+use super::{Level, LogError, Logger};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Whether the panic hook is installed.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Installs a panic hook that sends the panic message, location, thread name and a symbolized
+/// backtrace to the given logger at the error level, before aborting (in the release profile) or
+/// unwinding. The previous hook is invoked afterwards.
+///
+/// If a path is given, the same report is also written to this file, so that the app can read it
+/// with `take_crash_record()` after the next launch.
+///
+/// The hook can only be installed once per process, so that it doesn't report each panic several
+/// times when an activity is recreated.
+pub fn install_panic_hook<L: Logger + Send + Sync + 'static>(
+    logger: L,
+    crash_record_path: Option<PathBuf>,
+) -> io::Result<()> {
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the panic hook is already installed",
+        ));
+    }
+
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = format_report(info.payload(), info.location());
+        log_report(&logger, &report);
+        if let Some(path) = &crash_record_path {
+            if let Err(e) = write_crash_record(path, &report) {
+                log_report(
+                    &logger,
+                    &format!("Failed to write crash record to {path:?}: {e:?}"),
+                );
+            }
+        }
+        previous_hook(info);
+    }));
+    Ok(())
+}
+
+/// Logs a report from the panic hook. The panicking thread may hold the lock of the logger, if it
+/// panicked while logging, in which case waiting for it would deadlock: the report is written to
+/// stderr instead.
+fn log_report(logger: &impl Logger, report: &str) {
+    // There is nowhere to report a failure to log.
+    if let Err(e) = logger.try_log(Level::Error, report) {
+        if would_block(&e) {
+            let _ = writeln!(io::stderr(), "{report}");
+        }
+    }
+}
+
+fn would_block(e: &LogError) -> bool {
+    match e {
+        LogError::WouldBlock => true,
+        LogError::Multiple(errors) => errors.iter().any(would_block),
+        _ => false,
+    }
+}
+
+/// Returns the crash record written by the panic hook, if any, and removes it.
+pub fn take_crash_record(path: impl AsRef<Path>) -> io::Result<Option<String>> {
+    let path = path.as_ref();
+    match fs::read_to_string(path) {
+        Ok(record) => {
+            fs::remove_file(path)?;
+            Ok(Some(record))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn format_report(payload: &(dyn Any + Send), location: Option<&Location>) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+    let location = match location {
+        Some(location) => location.to_string(),
+        None => "unknown location".to_owned(),
+    };
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+
+    format!(
+        "Thread '{thread}' panicked at {location}:\n{message}\nBacktrace:\n{}",
+        Backtrace::force_capture()
+    )
+}
+
+fn write_crash_record(path: &Path, report: &str) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut file = File::create(path)?;
+    writeln!(file, "Crash at {timestamp} seconds since the Unix epoch")?;
+    file.write_all(report.as_bytes())?;
+    // The process is about to abort, so the record must reach the disk now.
+    file.sync_all()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::CaptureLogger;
+    use crate::testutil::run_in_subprocess;
+    use std::sync::{Arc, Mutex, TryLockError};
+
+    #[test]
+    fn test_format_report() {
+        let location = Location::caller();
+        let report = format_report(&"Oops", Some(location));
+        assert!(report.starts_with("Thread '"));
+        assert!(report.contains(&format!("' panicked at {location}:\nOops\nBacktrace:\n")));
+
+        let report = format_report(&"Oops".to_owned(), None);
+        assert!(report.contains("panicked at unknown location:\nOops\n"));
+    }
+
+    #[test]
+    fn test_panic_hook() {
+        // The panic hook is shared by all the tests running concurrently.
+        run_in_subprocess("logger::crash::tests::test_panic_hook", panic_hook_body);
+    }
+
+    fn panic_hook_body() {
+        // The hook installed by install_panic_hook() owns the previous one, so it is wrapped to be
+        // put back afterwards.
+        let previous_hook = Arc::new(std::panic::take_hook());
+        let chained_hook = previous_hook.clone();
+        std::panic::set_hook(Box::new(move |info| (*chained_hook)(info)));
+
+        let path = std::env::temp_dir().join(format!("simd-crash-{}.txt", std::process::id()));
+        let capture = Arc::new(CaptureLogger::new());
+        install_panic_hook(capture.clone(), Some(path.clone())).unwrap();
+        assert_eq!(
+            install_panic_hook(CaptureLogger::new(), None)
+                .err()
+                .unwrap()
+                .kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        let result = std::thread::Builder::new()
+            .name("crashing-thread".to_owned())
+            .spawn(|| panic!("Crash number {}", 42))
+            .unwrap()
+            .join();
+        drop(std::panic::take_hook());
+        std::panic::set_hook(Box::new(move |info| (*previous_hook)(info)));
+        assert!(result.is_err());
+
+        assert!(capture.contains(Level::Error, "Thread 'crashing-thread' panicked at src/"));
+        assert!(capture.contains(Level::Error, "Crash number 42\nBacktrace:\n"));
+        assert_eq!(capture.messages().len(), 1);
+
+        let record = take_crash_record(&path).unwrap().unwrap();
+        assert!(record.starts_with("Crash at "));
+        assert!(record.contains("Thread 'crashing-thread' panicked at src/"));
+        assert!(take_crash_record(&path).unwrap().is_none());
+    }
+
+    /// Logger whose lock is held by the thread that panics.
+    struct LockedLogger(Mutex<()>);
+
+    impl Logger for LockedLogger {
+        fn min_level(&self) -> Level {
+            Level::Verbose
+        }
+
+        fn write(&self, _: Level, _: &str) -> Result<(), LogError> {
+            let _guard = self.0.lock();
+            Ok(())
+        }
+
+        fn try_log(&self, _: Level, _: &str) -> Result<(), LogError> {
+            match self.0.try_lock() {
+                Err(TryLockError::WouldBlock) => Err(LogError::WouldBlock),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_panic_hook_while_logging() {
+        run_in_subprocess(
+            "logger::crash::tests::test_panic_hook_while_logging",
+            || {
+                let logger = Arc::new(LockedLogger(Mutex::new(())));
+                install_panic_hook(logger.clone(), None).unwrap();
+
+                // The hook doesn't wait for the lock, which would never be released.
+                let result = std::thread::spawn(move || {
+                    let _guard = logger.0.lock();
+                    panic!("Panic while logging");
+                })
+                .join();
+                assert!(result.is_err());
+            },
+        );
+    }
+}
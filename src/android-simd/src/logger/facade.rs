@@ -0,0 +1,205 @@
+//! Adaptor routing the records of the `log` crate's facade to one of our `Logger` backends.
+
+use super::{Level, Logger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::str::FromStr;
+
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Verbose,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseFilterError(String);
+
+/// Per-module filter for log records, parsed from a comma-separated list of directives in the
+/// same syntax as `env_logger`, e.g. `"warn,simd=debug,horcrux::shamir=trace"`.
+///
+/// A record is printed if its level is enabled by the directive whose module is the longest prefix
+/// of the record's target, or by the default level if no directive matches.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Level for targets that don't match any directive.
+    default: LevelFilter,
+    /// Module paths and their levels.
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Filter that lets all records through.
+    pub fn all() -> Self {
+        Self {
+            default: LevelFilter::Trace,
+            directives: Vec::new(),
+        }
+    }
+
+    pub fn parse(spec: &str) -> Result<Self, ParseFilterError> {
+        let mut result = Self {
+            default: LevelFilter::Error,
+            directives: Vec::new(),
+        };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let level = LevelFilter::from_str(level.trim())
+                        .map_err(|_| ParseFilterError(format!("Invalid level in {directive:?}")))?;
+                    result.directives.push((module.trim().to_owned(), level));
+                }
+                // A lone word is either a default level or a module with all levels enabled.
+                None => match LevelFilter::from_str(directive) {
+                    Ok(level) => result.default = level,
+                    Err(_) => result
+                        .directives
+                        .push((directive.to_owned(), LevelFilter::Trace)),
+                },
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the maximum level enabled for the given target.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(module, _)| {
+                target == module
+                    || (target.starts_with(module.as_str())
+                        && target[module.len()..].starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    /// Returns the maximum level enabled for any target.
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.default, std::cmp::max)
+    }
+}
+
+/// Implementation of the `log::Log` trait on top of a `Logger`. Records are printed with their
+/// target as a prefix.
+pub struct LogAdaptor<L> {
+    logger: L,
+    filter: LogFilter,
+}
+
+impl<L: Logger> LogAdaptor<L> {
+    pub fn new(logger: L, filter: LogFilter) -> Self {
+        Self { logger, filter }
+    }
+}
+
+impl<L: Logger + Send + Sync> Log for LogAdaptor<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
+            && Level::from(metadata.level()) >= self.logger.min_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            // There is nowhere to report a failure to log.
+            let _ = self.logger.log(
+                record.level().into(),
+                &format!("{}: {}", record.target(), record.args()),
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the given logger as the global logger of the `log` crate. This fails if a global
+/// logger was already installed.
+pub fn init<L: Logger + Send + Sync + 'static>(
+    logger: L,
+    filter: LogFilter,
+) -> Result<(), SetLoggerError> {
+    let max_level = filter.max_level();
+    log::set_boxed_logger(Box::new(LogAdaptor::new(logger, filter)))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::CaptureLogger;
+
+    fn log(adaptor: &impl Log, level: log::Level, target: &str, message: &str) {
+        adaptor.log(
+            &Record::builder()
+                .level(level)
+                .target(target)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            LogFilter::parse("warn, simd=debug,horcrux::shamir").unwrap(),
+            LogFilter {
+                default: LevelFilter::Warn,
+                directives: vec![
+                    ("simd".to_owned(), LevelFilter::Debug),
+                    ("horcrux::shamir".to_owned(), LevelFilter::Trace),
+                ],
+            }
+        );
+        assert_eq!(
+            LogFilter::parse("").unwrap().max_level(),
+            LevelFilter::Error
+        );
+        assert!(LogFilter::parse("simd=loud").is_err());
+    }
+
+    #[test]
+    fn test_level_for() {
+        let filter = LogFilter::parse("info,simd=debug,simd::pmul=off,horcrux").unwrap();
+        assert_eq!(filter.level_for("simd"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("simd::cpu"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("simd::pmul"), LevelFilter::Off);
+        assert_eq!(filter.level_for("simd::pmul::tests"), LevelFilter::Off);
+        assert_eq!(filter.level_for("simdx"), LevelFilter::Info);
+        assert_eq!(filter.level_for("horcrux::gf2n"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("jni"), LevelFilter::Info);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn test_log_adaptor() {
+        let adaptor = LogAdaptor::new(
+            CaptureLogger::new().with_min_level(Level::Debug),
+            LogFilter::parse("warn,simd=trace").unwrap(),
+        );
+        log(&adaptor, log::Level::Info, "horcrux", "filtered by module");
+        log(&adaptor, log::Level::Error, "horcrux", "error");
+        log(
+            &adaptor,
+            log::Level::Trace,
+            "simd::cpu",
+            "filtered by logger",
+        );
+        log(&adaptor, log::Level::Debug, "simd::cpu", "debug");
+
+        assert_eq!(
+            adaptor.logger.messages(),
+            [
+                (Level::Error, "horcrux: error".to_owned()),
+                (Level::Debug, "simd::cpu: debug".to_owned()),
+            ]
+        );
+    }
+}
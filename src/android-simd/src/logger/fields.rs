@@ -0,0 +1,190 @@
+//! Structured key-value fields attached to log records.
+
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+/// A structured field, as a key and a value.
+pub type Field<'a> = (&'a str, Value<'a>);
+
+/// Value of a structured field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value<'a> {
+    Str(&'a str),
+    String(String),
+    U64(u64),
+    Duration(Duration),
+    List(&'a [&'a str]),
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Str(value)
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<u64> for Value<'_> {
+    fn from(value: u64) -> Self {
+        Value::U64(value)
+    }
+}
+
+impl From<Duration> for Value<'_> {
+    fn from(value: Duration) -> Self {
+        Value::Duration(value)
+    }
+}
+
+impl<'a> From<&'a [&'a str]> for Value<'a> {
+    fn from(value: &'a [&'a str]) -> Self {
+        Value::List(value)
+    }
+}
+
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => f.write_str(s),
+            Value::String(s) => f.write_str(s),
+            Value::U64(x) => write!(f, "{x}"),
+            Value::Duration(d) => write!(f, "{d:?}"),
+            Value::List(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str(item)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// Formats a message and its fields as text, in the form `message [key = value, ...]`.
+pub(super) fn format_text(message: &str, fields: &[Field]) -> String {
+    if fields.is_empty() {
+        return message.to_owned();
+    }
+    let mut result = format!("{message} [");
+    for (i, (key, value)) in fields.iter().enumerate() {
+        if i != 0 {
+            result.push_str(", ");
+        }
+        result.push_str(&format!("{key} = {value}"));
+    }
+    result.push(']');
+    result
+}
+
+/// Formats a record as a single line of JSON (without the trailing newline), with the fields in a
+/// nested object. Durations are written as a number of nanoseconds.
+pub(super) fn format_json(timestamp: &str, level: char, message: &str, fields: &[Field]) -> String {
+    let mut result = String::new();
+    result.push_str("{\"timestamp\":");
+    push_json_string(&mut result, timestamp);
+    result.push_str(",\"level\":\"");
+    result.push(level);
+    result.push_str("\",\"message\":");
+    push_json_string(&mut result, message);
+    result.push_str(",\"fields\":{");
+    for (i, (key, value)) in fields.iter().enumerate() {
+        if i != 0 {
+            result.push(',');
+        }
+        push_json_string(&mut result, key);
+        result.push(':');
+        match value {
+            Value::Str(s) => push_json_string(&mut result, s),
+            Value::String(s) => push_json_string(&mut result, s),
+            Value::U64(x) => result.push_str(&x.to_string()),
+            Value::Duration(d) => result.push_str(&d.as_nanos().to_string()),
+            Value::List(items) => {
+                result.push('[');
+                for (j, item) in items.iter().enumerate() {
+                    if j != 0 {
+                        result.push(',');
+                    }
+                    push_json_string(&mut result, item);
+                }
+                result.push(']');
+            }
+        }
+    }
+    result.push_str("}}");
+    result
+}
+
+fn push_json_string(result: &mut String, s: &str) {
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_text() {
+        assert_eq!(format_text("message", &[]), "message");
+        assert_eq!(
+            format_text(
+                "Computed",
+                &[
+                    ("operation", "pmul".into()),
+                    ("count", 3u64.into()),
+                    ("duration", Duration::from_micros(5).into()),
+                    ("features", (&["aes", "pmull"][..]).into()),
+                ]
+            ),
+            "Computed [operation = pmul, count = 3, duration = 5µs, features = [aes, pmull]]"
+        );
+    }
+
+    #[test]
+    fn test_format_json() {
+        assert_eq!(
+            format_json("1970-01-01 00:00:00.000", 'I', "message", &[]),
+            r#"{"timestamp":"1970-01-01 00:00:00.000","level":"I","message":"message","fields":{}}"#
+        );
+        assert_eq!(
+            format_json(
+                "1970-01-01 00:00:00.000",
+                'D',
+                "Computed",
+                &[
+                    ("operation", "pmul".into()),
+                    ("output", "0e03".to_owned().into()),
+                    ("count", 3u64.into()),
+                    ("duration", Duration::from_micros(5).into()),
+                    ("features", (&["aes", "pmull"][..]).into()),
+                ]
+            ),
+            r#"{"timestamp":"1970-01-01 00:00:00.000","level":"D","message":"Computed","fields":{"operation":"pmul","output":"0e03","count":3,"duration":5000,"features":["aes","pmull"]}}"#
+        );
+    }
+
+    #[test]
+    fn test_json_escape() {
+        let mut result = String::new();
+        push_json_string(&mut result, "a \"quoted\" \\ line\nwith\ttab\u{1}é");
+        assert_eq!(result, r#""a \"quoted\" \\ line\nwith\ttab\u0001é""#);
+    }
+}
@@ -0,0 +1,372 @@
+//! Logger backend writing to rotating files.
+
+use super::{fields, Field, Level, LogError, Logger};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Logger that writes timestamped lines to `<name>.log` in a given directory, or JSON lines to
+/// `<name>.jsonl`.
+///
+/// Once the file would exceed the maximum size, it is renamed to `<name>.1.log`, the previous
+/// `<name>.1.log` to `<name>.2.log`, and so on, and the oldest file is deleted to keep at most the
+/// maximum number of files. Files are synced to the disk after each error-level record.
+pub struct FileLogger {
+    /// Directory containing the log files.
+    dir: PathBuf,
+    /// Base name of the log files.
+    name: String,
+    /// Whether records are written as JSON lines rather than text.
+    json_lines: bool,
+    /// Size in bytes above which the current file is rotated.
+    max_file_size: u64,
+    /// Maximum number of files, including the current one.
+    max_files: usize,
+    /// Messages below this level are discarded.
+    min_level: Level,
+    /// Current file.
+    state: Mutex<FileState>,
+}
+
+struct FileState {
+    file: File,
+    size: u64,
+}
+
+impl FileLogger {
+    /// Default size in bytes above which files are rotated.
+    pub const DEFAULT_MAX_FILE_SIZE: u64 = 1 << 20;
+    /// Default maximum number of files.
+    pub const DEFAULT_MAX_FILES: usize = 5;
+
+    /// Creates a logger appending to `<name>.log` in the given directory, which is created if
+    /// needed.
+    pub fn new(dir: impl Into<PathBuf>, name: &str) -> io::Result<Self> {
+        Self::open(dir.into(), name, false)
+    }
+
+    /// Creates a logger appending to `<name>.jsonl` in the given directory, which is created if
+    /// needed. Each record is written as a JSON object on its own line, with the keys
+    /// `timestamp`, `level`, `message` and `fields`.
+    pub fn new_json_lines(dir: impl Into<PathBuf>, name: &str) -> io::Result<Self> {
+        Self::open(dir.into(), name, true)
+    }
+
+    fn open(dir: PathBuf, name: &str, json_lines: bool) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let extension = if json_lines { "jsonl" } else { "log" };
+        let file = open_append(&dir.join(format!("{name}.{extension}")))?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            dir,
+            name: name.to_owned(),
+            json_lines,
+            max_file_size: Self::DEFAULT_MAX_FILE_SIZE,
+            max_files: Self::DEFAULT_MAX_FILES,
+            min_level: Level::Verbose,
+            state: Mutex::new(FileState { file, size }),
+        })
+    }
+
+    /// Rotates files once they would exceed the given size in bytes.
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Keeps at most the given number of files, including the current one.
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        assert!(max_files >= 1, "At least one log file is needed");
+        self.max_files = max_files;
+        self
+    }
+
+    /// Discards messages below the given level.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Path of the i-th file, the current one being the 0-th.
+    pub fn path(&self, i: usize) -> PathBuf {
+        let extension = if self.json_lines { "jsonl" } else { "log" };
+        if i == 0 {
+            self.dir.join(format!("{}.{extension}", self.name))
+        } else {
+            self.dir.join(format!("{}.{i}.{extension}", self.name))
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FileState> {
+        // The state remains consistent even if another thread panicked while holding the lock.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn try_lock(&self) -> Result<MutexGuard<'_, FileState>, LogError> {
+        match self.state.try_lock() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(e)) => Ok(e.into_inner()),
+            Err(TryLockError::WouldBlock) => Err(LogError::WouldBlock),
+        }
+    }
+
+    fn rotate(&self, state: &mut FileState) -> io::Result<()> {
+        state.file.sync_all()?;
+        for i in (0..self.max_files - 1).rev() {
+            match fs::rename(self.path(i), self.path(i + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => (),
+            }
+        }
+        if self.max_files == 1 {
+            fs::remove_file(self.path(0))?;
+        }
+        state.file = open_append(&self.path(0))?;
+        state.size = 0;
+        Ok(())
+    }
+
+    fn format_lines(&self, level: Level, message: &str, fields: &[Field]) -> String {
+        let timestamp = format_timestamp(SystemTime::now());
+        let mut lines = String::new();
+        if self.json_lines {
+            lines.push_str(&fields::format_json(
+                &timestamp,
+                level.letter(),
+                message,
+                fields,
+            ));
+            lines.push('\n');
+        } else {
+            let message = fields::format_text(message, fields);
+            for line in message.split('\n') {
+                lines.push_str(&format!("{timestamp} {} {line}\n", level.letter()));
+            }
+        }
+        lines
+    }
+
+    fn append(&self, state: &mut FileState, level: Level, lines: &str) -> Result<(), LogError> {
+        if state.size > 0 && state.size + lines.len() as u64 > self.max_file_size {
+            self.rotate(state)?;
+        }
+        state.file.write_all(lines.as_bytes())?;
+        state.size += lines.len() as u64;
+        if level >= Level::Error {
+            state.file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+impl Logger for FileLogger {
+    fn min_level(&self) -> Level {
+        self.min_level
+    }
+
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        let lines = self.format_lines(level, message, &[]);
+        self.append(&mut self.lock(), level, &lines)
+    }
+
+    fn try_log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        if level < self.min_level {
+            return Ok(());
+        }
+        let lines = self.format_lines(level, message, &[]);
+        self.append(&mut *self.try_lock()?, level, &lines)
+    }
+
+    /// In the JSON-lines format, the fields are written as a nested object.
+    fn log_fields(&self, level: Level, message: &str, fields: &[Field]) -> Result<(), LogError> {
+        if level < self.min_level {
+            return Ok(());
+        }
+        let lines = self.format_lines(level, message, fields);
+        self.append(&mut self.lock(), level, &lines)
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Formats a time as `YYYY-MM-DD hh:mm:ss.mmm` in UTC.
+fn format_timestamp(time: SystemTime) -> String {
+    let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = duration.as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Conversion of a number of days since 1970-01-01 to a date in the proleptic Gregorian
+    // calendar, from http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:03}",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        duration.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::LoggerExt;
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("simd-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01 00:00:00.000");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_millis(951_782_400_123)),
+            "2000-02-29 00:00:00.123"
+        );
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_672_531_199)),
+            "2022-12-31 23:59:59.000"
+        );
+    }
+
+    #[test]
+    fn test_file_logger() {
+        let dir = temp_dir("file-logger");
+        let logger = FileLogger::new(&dir, "app")
+            .unwrap()
+            .with_min_level(Level::Debug);
+        logger.v("verbose").unwrap();
+        logger.d("debug").unwrap();
+        logger.e("first line\nsecond line").unwrap();
+
+        let contents = fs::read_to_string(dir.join("app.log")).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        // The timestamp takes 23 characters.
+        assert_eq!(&lines[0][23..], " D debug");
+        assert_eq!(&lines[1][23..], " E first line");
+        assert_eq!(&lines[2][23..], " E second line");
+
+        // A new logger appends to the existing file.
+        drop(logger);
+        let logger = FileLogger::new(&dir, "app").unwrap();
+        logger.i("appended").unwrap();
+        let contents = fs::read_to_string(dir.join("app.log")).unwrap();
+        assert_eq!(contents.lines().count(), 4);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_logger_try_log() {
+        let dir = temp_dir("file-logger-try-log");
+        let logger = FileLogger::new(&dir, "app").unwrap();
+        logger.try_log(Level::Info, "unlocked").unwrap();
+        let guard = logger.lock();
+        assert!(matches!(
+            logger.try_log(Level::Info, "locked"),
+            Err(LogError::WouldBlock)
+        ));
+        drop(guard);
+
+        let contents = fs::read_to_string(dir.join("app.log")).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(contents.ends_with(" I unlocked\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_logger_json_lines() {
+        let dir = temp_dir("file-logger-json");
+        let logger = FileLogger::new_json_lines(&dir, "app").unwrap();
+        logger.i("plain \"message\"").unwrap();
+        logger
+            .log_fields(
+                Level::Debug,
+                "Computed",
+                &[("strategy", "nosimd".into()), ("count", 2u64.into())],
+            )
+            .unwrap();
+
+        assert_eq!(logger.path(0), dir.join("app.jsonl"));
+        assert_eq!(logger.path(1), dir.join("app.1.jsonl"));
+        let contents = fs::read_to_string(logger.path(0)).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        // The timestamp takes 23 characters, after the 14 characters of the timestamp key.
+        assert!(lines[0].starts_with(r#"{"timestamp":""#));
+        assert_eq!(
+            &lines[0][37..],
+            r#"","level":"I","message":"plain \"message\"","fields":{}}"#
+        );
+        assert_eq!(
+            &lines[1][37..],
+            r#"","level":"D","message":"Computed","fields":{"strategy":"nosimd","count":2}}"#
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_logger_rotation() {
+        let dir = temp_dir("file-logger-rotation");
+        // Each line is 23 + 3 + 2 + 1 = 29 bytes long, so that 2 lines fit in a file.
+        let logger = FileLogger::new(&dir, "app")
+            .unwrap()
+            .with_max_file_size(60)
+            .with_max_files(3);
+        for i in 0..9 {
+            logger.i(format!("{i:02}")).unwrap();
+        }
+
+        let read = |i| {
+            fs::read_to_string(logger.path(i))
+                .unwrap()
+                .lines()
+                .map(|line| line[26..].to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(read(0), ["08"]);
+        assert_eq!(read(1), ["06", "07"]);
+        assert_eq!(read(2), ["04", "05"]);
+        assert!(!logger.path(3).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_logger_single_file() {
+        let dir = temp_dir("file-logger-single");
+        let logger = FileLogger::new(&dir, "app")
+            .unwrap()
+            .with_max_file_size(60)
+            .with_max_files(1);
+        for i in 0..3 {
+            logger.i(format!("{i:02}")).unwrap();
+        }
+
+        let contents = fs::read_to_string(logger.path(0)).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(contents.ends_with(" I 02\n"));
+        assert!(!logger.path(1).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
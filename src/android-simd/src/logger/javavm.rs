@@ -0,0 +1,81 @@
+//! Thread-safe logger calling the android.util.Log class through JNI.
+
+use super::{Level, LogError, Logger, LOGCAT_MAX_MESSAGE_LEN};
+use jni::objects::{GlobalRef, JValue};
+use jni::{AttachGuard, JNIEnv, JavaVM};
+
+/// Logger calling the static methods of the android.util.Log class. Contrary to the
+/// `AndroidLogger`, this isn't tied to the JNI environment of the calling thread: each message is
+/// printed from a JNI environment obtained by attaching the current thread to the Java VM.
+pub struct JavaVmLogger {
+    /// Java VM, to obtain a JNI environment on any thread.
+    vm: JavaVM,
+    /// Global reference to the android.util.Log class.
+    log_class: GlobalRef,
+    /// Global reference to the tag for log messages.
+    tag: GlobalRef,
+    /// Messages below this level are discarded.
+    min_level: Level,
+}
+
+impl JavaVmLogger {
+    pub fn new(env: JNIEnv, tag: &str) -> Result<Self, jni::errors::Error> {
+        let log_class = env.auto_local(env.find_class("android/util/Log")?);
+        let tag = env.auto_local(env.new_string(tag)?);
+        Ok(Self {
+            vm: env.get_java_vm()?,
+            log_class: env.new_global_ref(&log_class)?,
+            tag: env.new_global_ref(&tag)?,
+            min_level: Level::Verbose,
+        })
+    }
+
+    /// Discards messages below the given level.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Attaches the current thread to the Java VM until the returned guard is dropped, if it isn't
+    /// attached already.
+    ///
+    /// Otherwise, a thread that isn't attached is attached and detached again for every message,
+    /// which is expensive. Worker threads that print many messages should hold this guard.
+    pub fn attach_current_thread(&self) -> Result<AttachGuard<'_>, jni::errors::Error> {
+        self.vm.attach_current_thread()
+    }
+}
+
+// The logger must be usable from any thread.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<JavaVmLogger>();
+};
+
+impl Logger for JavaVmLogger {
+    fn min_level(&self) -> Level {
+        self.min_level
+    }
+
+    fn max_message_len(&self) -> Option<usize> {
+        Some(LOGCAT_MAX_MESSAGE_LEN)
+    }
+
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        // If the thread wasn't attached yet, it is detached again when this guard is dropped.
+        let env = self.vm.attach_current_thread()?;
+        // The local reference must be deleted explicitly, because control may not return to Java
+        // before the local reference table overflows.
+        let message = env.auto_local(env.new_string(message)?);
+        env.call_static_method(
+            &self.log_class,
+            level.android_method(),
+            "(Ljava/lang/String;Ljava/lang/String;)I",
+            &[
+                JValue::Object(self.tag.as_obj()),
+                JValue::Object(message.as_obj()),
+            ],
+        )?;
+        Ok(())
+    }
+}
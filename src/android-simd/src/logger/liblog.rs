@@ -0,0 +1,215 @@
+//! Logger backend that writes directly to Android's liblog, without going through JNI.
+//!
+//! On other platforms, liblog is replaced by a stand-in that writes to stderr in the same format
+//! as `logcat -v brief`.
+
+use super::{Level, LogError, Logger, LOGCAT_MAX_MESSAGE_LEN};
+use std::ffi::{CStr, CString, NulError};
+use std::os::raw::c_int;
+
+/// Log buffers of liblog, as defined by `log_id_t` in `<android/log.h>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogBuffer {
+    Main = 0,
+    Radio = 1,
+    Events = 2,
+    System = 3,
+    Crash = 4,
+}
+
+impl Level {
+    /// Priority value, as defined by `android_LogPriority` in `<android/log.h>`.
+    fn android_priority(self) -> c_int {
+        match self {
+            Level::Verbose => 2,
+            Level::Debug => 3,
+            Level::Info => 4,
+            Level::Warn => 5,
+            Level::Error => 6,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LiblogError {
+    /// The tag or the message contains a null byte.
+    Nul(NulError),
+    /// liblog returned a negative status code.
+    Status(c_int),
+}
+
+impl From<NulError> for LiblogError {
+    fn from(e: NulError) -> Self {
+        LiblogError::Nul(e)
+    }
+}
+
+/// Logger calling liblog's `__android_log_write()` and `__android_log_buf_write()`. Contrary to
+/// the `AndroidLogger`, this can be used from any thread.
+pub struct LiblogLogger {
+    /// Tag for log messages.
+    tag: CString,
+    /// Buffer to write to, or `None` for the default buffer.
+    buffer: Option<LogBuffer>,
+    /// Messages below this level are discarded.
+    min_level: Level,
+}
+
+impl LiblogLogger {
+    pub fn new(tag: &str) -> Result<Self, NulError> {
+        Ok(Self {
+            tag: CString::new(tag)?,
+            buffer: None,
+            min_level: Level::Verbose,
+        })
+    }
+
+    /// Writes messages to the given buffer rather than the default one.
+    pub fn with_buffer(mut self, buffer: LogBuffer) -> Self {
+        self.buffer = Some(buffer);
+        self
+    }
+
+    /// Discards messages below the given level.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+}
+
+impl Logger for LiblogLogger {
+    fn min_level(&self) -> Level {
+        self.min_level
+    }
+
+    fn max_message_len(&self) -> Option<usize> {
+        Some(LOGCAT_MAX_MESSAGE_LEN)
+    }
+
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        let text = CString::new(message).map_err(LiblogError::Nul)?;
+        let prio = level.android_priority();
+        // Safety: tag and text are valid null-terminated strings.
+        let status = unsafe {
+            match self.buffer {
+                None => ffi::__android_log_write(prio, self.tag.as_ptr(), text.as_ptr()),
+                Some(buffer) => ffi::__android_log_buf_write(
+                    buffer as c_int,
+                    prio,
+                    self.tag.as_ptr(),
+                    text.as_ptr(),
+                ),
+            }
+        };
+        if status < 0 {
+            Err(LiblogError::Status(status).into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+mod ffi {
+    use std::os::raw::{c_char, c_int};
+
+    #[link(name = "log")]
+    extern "C" {
+        pub fn __android_log_write(prio: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+        pub fn __android_log_buf_write(
+            buf_id: c_int,
+            prio: c_int,
+            tag: *const c_char,
+            text: *const c_char,
+        ) -> c_int;
+    }
+}
+
+/// Stand-in for liblog, writing to stderr.
+#[cfg(not(target_os = "android"))]
+#[allow(non_snake_case)]
+mod ffi {
+    use super::format_brief;
+    use std::ffi::CStr;
+    use std::io::Write;
+    use std::os::raw::{c_char, c_int};
+
+    pub unsafe fn __android_log_write(
+        prio: c_int,
+        tag: *const c_char,
+        text: *const c_char,
+    ) -> c_int {
+        __android_log_buf_write(0, prio, tag, text)
+    }
+
+    pub unsafe fn __android_log_buf_write(
+        _buf_id: c_int,
+        prio: c_int,
+        tag: *const c_char,
+        text: *const c_char,
+    ) -> c_int {
+        // Safety: the caller passes valid null-terminated strings.
+        let line = format_brief(prio, CStr::from_ptr(tag), CStr::from_ptr(text));
+        match std::io::stderr().lock().write_all(line.as_bytes()) {
+            Ok(()) => line.len() as c_int,
+            Err(_) => -5, // -EIO
+        }
+    }
+}
+
+/// Formats a message in the same way as `logcat -v brief`, with one line per line of the message.
+#[cfg_attr(target_os = "android", allow(dead_code))]
+fn format_brief(prio: c_int, tag: &CStr, text: &CStr) -> String {
+    let prio = match prio {
+        2 => 'V',
+        3 => 'D',
+        4 => 'I',
+        5 => 'W',
+        6 => 'E',
+        7 => 'F',
+        _ => '?',
+    };
+    let tag = tag.to_string_lossy();
+    let pid = std::process::id();
+
+    let mut result = String::new();
+    for line in text.to_string_lossy().split('\n') {
+        result.push_str(&format!("{prio}/{tag}({pid:5}): {line}\n"));
+    }
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::LoggerExt;
+
+    #[test]
+    fn test_format_brief() {
+        let tag = CString::new("MyTag").unwrap();
+        let text = CString::new("Hello\nworld").unwrap();
+        let pid = std::process::id();
+        assert_eq!(
+            format_brief(Level::Warn.android_priority(), &tag, &text),
+            format!("W/MyTag({pid:5}): Hello\nW/MyTag({pid:5}): world\n")
+        );
+    }
+
+    #[test]
+    fn test_liblog_logger() {
+        let logger = LiblogLogger::new("MyRustSimdApplication").unwrap();
+        logger.i("Hello from liblog").unwrap();
+
+        let logger = logger.with_buffer(LogBuffer::Main);
+        logger.d("Hello from liblog's main buffer").unwrap();
+    }
+
+    #[test]
+    fn test_liblog_logger_nul() {
+        let logger = LiblogLogger::new("MyRustSimdApplication").unwrap();
+        assert!(matches!(
+            logger.i("Hello\0world"),
+            Err(LogError::Liblog(LiblogError::Nul(_)))
+        ));
+    }
+}
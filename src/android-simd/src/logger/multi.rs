@@ -0,0 +1,149 @@
+//! Logger that fans out each message to several backends.
+
+use super::{Field, Level, LogError, Logger};
+
+/// Logger that prints each message to all of its backends. Each backend applies its own minimum
+/// level and maximum message length.
+///
+/// The backends are `dyn Logger` by default, but can be restricted further, for example to
+/// `dyn Logger + Send + Sync` to install the result as the global logger of the `log` crate.
+pub struct MultiLogger<L: Logger + ?Sized = dyn Logger> {
+    loggers: Vec<Box<L>>,
+}
+
+impl<L: Logger + ?Sized> MultiLogger<L> {
+    pub fn new() -> Self {
+        Self {
+            loggers: Vec::new(),
+        }
+    }
+
+    /// Adds a backend.
+    pub fn with(mut self, logger: Box<L>) -> Self {
+        self.loggers.push(logger);
+        self
+    }
+
+    /// Adds a backend.
+    pub fn push(&mut self, logger: Box<L>) {
+        self.loggers.push(logger);
+    }
+
+    /// Calls the given function on all the backends, even if some of them fail.
+    fn for_each(&self, f: impl Fn(&L) -> Result<(), LogError>) -> Result<(), LogError> {
+        let mut errors = Vec::new();
+        for logger in &self.loggers {
+            if let Err(e) = f(logger) {
+                errors.push(e);
+            }
+        }
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.pop().unwrap()),
+            _ => Err(LogError::Multiple(errors)),
+        }
+    }
+}
+
+impl<L: Logger + ?Sized> Default for MultiLogger<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: Logger + ?Sized> Logger for MultiLogger<L> {
+    fn min_level(&self) -> Level {
+        self.loggers
+            .iter()
+            .map(|logger| logger.min_level())
+            .min()
+            .unwrap_or(Level::Error)
+    }
+
+    /// Prints the message to all the backends, even if some of them fail.
+    fn write(&self, level: Level, message: &str) -> Result<(), LogError> {
+        self.for_each(|logger| logger.log(level, message))
+    }
+
+    fn try_log(&self, level: Level, message: &str) -> Result<(), LogError> {
+        self.for_each(|logger| logger.try_log(level, message))
+    }
+
+    /// Forwards the fields to all the backends, so that each of them records them in its own way.
+    fn log_fields(&self, level: Level, message: &str, fields: &[Field]) -> Result<(), LogError> {
+        self.for_each(|logger| logger.log_fields(level, message, fields))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::{CaptureLogger, LiblogError, LoggerExt};
+
+    struct FailingLogger;
+
+    impl Logger for FailingLogger {
+        fn min_level(&self) -> Level {
+            Level::Verbose
+        }
+
+        fn write(&self, _level: Level, _message: &str) -> Result<(), LogError> {
+            Err(LiblogError::Status(-1).into())
+        }
+    }
+
+    #[test]
+    fn test_multi_logger() {
+        let debug = CaptureLogger::new().with_min_level(Level::Debug);
+        let warn = CaptureLogger::new().with_min_level(Level::Warn);
+        let logger = MultiLogger::<dyn Logger>::new()
+            .with(Box::new(&debug))
+            .with(Box::new(&warn));
+        assert_eq!(logger.min_level(), Level::Debug);
+
+        logger.v("verbose").unwrap();
+        logger.i("info").unwrap();
+        logger.e("error").unwrap();
+
+        assert_eq!(
+            debug.messages(),
+            [
+                (Level::Info, "info".to_owned()),
+                (Level::Error, "error".to_owned()),
+            ]
+        );
+        assert_eq!(warn.messages(), [(Level::Error, "error".to_owned())]);
+
+        logger
+            .log_fields(Level::Info, "fields", &[("key", "value".into())])
+            .unwrap();
+        let records = debug.records();
+        assert_eq!(records[2].message, "fields");
+        assert_eq!(records[2].field("key"), Some("value"));
+        assert_eq!(warn.records().len(), 1);
+    }
+
+    #[test]
+    fn test_multi_logger_errors() {
+        let capture = CaptureLogger::new();
+        let mut logger: MultiLogger<dyn Logger + '_> = MultiLogger::new();
+        logger.push(Box::new(FailingLogger));
+        logger.push(Box::new(&capture));
+        assert!(matches!(logger.i("info"), Err(LogError::Liblog(_))));
+
+        logger.push(Box::new(FailingLogger));
+        assert!(matches!(logger.i("info"), Err(LogError::Multiple(e)) if e.len() == 2));
+
+        // The working backend received both messages.
+        assert_eq!(capture.records().len(), 2);
+    }
+
+    #[test]
+    fn test_multi_logger_send_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let logger: MultiLogger<dyn Logger + Send + Sync> =
+            MultiLogger::new().with(Box::new(CaptureLogger::new()));
+        assert_send_sync(&logger);
+    }
+}
@@ -0,0 +1,289 @@
+//! Redirection of the process' stdout and stderr to a logger.
+
+use super::{Level, Logger};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::raw::c_int;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Whether stdout and stderr are currently redirected.
+static REDIRECTED: AtomicBool = AtomicBool::new(false);
+
+/// Redirection of one of the standard file descriptors.
+struct Redirection {
+    /// Standard file descriptor that is redirected.
+    fd: RawFd,
+    /// Duplicate of the original file descriptor, restored when the redirection stops.
+    saved: RawFd,
+    /// Write end of a second pipe, closed to tell the reader thread to stop.
+    stop: File,
+    /// Thread reading from the pipe.
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Handle on the redirection of stdout and stderr, created by `redirect_stdio()`. The redirection
+/// stops when this handle is dropped.
+pub struct StdioRedirect {
+    redirections: Vec<Redirection>,
+}
+
+/// Redirects stdout and stderr to the given logger, at the info and warn levels respectively. This
+/// makes the output of `println!()`, panic messages and dependencies visible in logcat.
+///
+/// Each line is forwarded as a separate message, by a thread reading from a pipe that replaces the
+/// standard file descriptor. The logger must not itself write to stdout or stderr.
+pub fn redirect_stdio<L: Logger + Send + Sync + 'static>(logger: L) -> io::Result<StdioRedirect> {
+    if REDIRECTED.swap(true, Ordering::SeqCst) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "stdout and stderr are already redirected",
+        ));
+    }
+
+    let logger = Arc::new(logger);
+    let mut result = StdioRedirect {
+        redirections: Vec::new(),
+    };
+    for (fd, level) in [
+        (libc::STDOUT_FILENO, Level::Info),
+        (libc::STDERR_FILENO, Level::Warn),
+    ] {
+        match redirect(fd, level, logger.clone()) {
+            Ok(redirection) => result.redirections.push(redirection),
+            Err(e) => {
+                // Stop the redirections created so far.
+                drop(result);
+                REDIRECTED.store(false, Ordering::SeqCst);
+                return Err(e);
+            }
+        }
+    }
+    Ok(result)
+}
+
+impl StdioRedirect {
+    /// Restores the original stdout and stderr, after all the pending output has been forwarded
+    /// to the logger.
+    pub fn stop(mut self) -> io::Result<()> {
+        self.stop_impl()
+    }
+
+    fn stop_impl(&mut self) -> io::Result<()> {
+        if self.redirections.is_empty() {
+            return Ok(());
+        }
+
+        // Flush the data buffered by Rust into the pipes.
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+
+        let mut result = Ok(());
+        for mut redirection in self.redirections.drain(..) {
+            // Restoring the original file descriptor closes the write end of the pipe. The reader
+            // thread may still not receive end-of-file, if another process or a dup() keeps the
+            // write end open, so closing the stop pipe tells it to forward the data remaining in
+            // the pipe and return.
+            if let Err(e) = check(unsafe { libc::dup2(redirection.saved, redirection.fd) }) {
+                result = Err(e);
+                continue;
+            }
+            unsafe { libc::close(redirection.saved) };
+            drop(redirection.stop);
+            if let Some(thread) = redirection.thread.take() {
+                let _ = thread.join();
+            }
+        }
+        REDIRECTED.store(false, Ordering::SeqCst);
+        result
+    }
+}
+
+impl Drop for StdioRedirect {
+    fn drop(&mut self) {
+        let _ = self.stop_impl();
+    }
+}
+
+fn redirect<L: Logger + Send + Sync + 'static>(
+    fd: RawFd,
+    level: Level,
+    logger: Arc<L>,
+) -> io::Result<Redirection> {
+    let (reader, writer) = pipe()?;
+    let (stop_reader, stop) = pipe()?;
+
+    let saved = check(unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) })?;
+    let status = check(unsafe { libc::dup2(writer.as_raw_fd(), fd) });
+    // The write end now remains open only as the standard file descriptor.
+    drop(writer);
+    if let Err(e) = status {
+        unsafe { libc::close(saved) };
+        return Err(e);
+    }
+
+    let thread = std::thread::Builder::new()
+        .name(format!("stdio-redirect-{fd}"))
+        .spawn(move || forward_lines(reader, stop_reader, level, &*logger));
+    match thread {
+        Ok(thread) => Ok(Redirection {
+            fd,
+            saved,
+            stop,
+            thread: Some(thread),
+        }),
+        Err(e) => {
+            unsafe {
+                libc::dup2(saved, fd);
+                libc::close(saved);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Creates a pipe, whose ends are closed on exec. Returns the read and write ends.
+fn pipe() -> io::Result<(File, File)> {
+    let mut fds: [c_int; 2] = [-1; 2];
+    // Safety: fds points to an array of 2 file descriptors.
+    check(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) })?;
+    // Safety: the file descriptors are valid, and owned by these Files from now on.
+    Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+}
+
+/// Forwards each line read from the pipe to the logger, until end-of-file or until the write end
+/// of the stop pipe is closed. In the latter case, the data already in the pipe is forwarded
+/// without waiting for end-of-file, which doesn't come while another copy of the write end is open.
+fn forward_lines(mut pipe: File, stop: File, level: Level, logger: &dyn Logger) {
+    let mut pending = Vec::new();
+    let mut buffer = [0; 4096];
+    let mut stopping = false;
+    loop {
+        if !stopping {
+            let mut fds = [pipe.as_raw_fd(), stop.as_raw_fd()].map(|fd| libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            });
+            // Safety: fds points to an array of 2 pollfd structures.
+            match check(unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) }) {
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+            if fds[1].revents != 0 {
+                stopping = true;
+                // From now on, reading returns an error once the pipe is empty.
+                if check(unsafe { libc::fcntl(pipe.as_raw_fd(), libc::F_SETFL, libc::O_NONBLOCK) })
+                    .is_err()
+                {
+                    break;
+                }
+            } else if fds[0].revents == 0 {
+                continue;
+            }
+        }
+
+        match pipe.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => {
+                pending.extend_from_slice(&buffer[..len]);
+                while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    forward_line(&line[..end], level, logger);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+    }
+    if !pending.is_empty() {
+        forward_line(&pending, level, logger);
+    }
+}
+
+fn forward_line(line: &[u8], level: Level, logger: &dyn Logger) {
+    // There is nowhere to report a failure to log.
+    let _ = logger.log(level, &String::from_utf8_lossy(line));
+}
+
+fn check(status: c_int) -> io::Result<c_int> {
+    if status < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::CaptureLogger;
+    use crate::testutil::run_in_subprocess;
+
+    #[test]
+    fn test_redirect_stdio() {
+        // The standard file descriptors are shared by all the tests running concurrently.
+        run_in_subprocess(
+            "logger::redirect::tests::test_redirect_stdio",
+            redirect_stdio_body,
+        );
+    }
+
+    fn redirect_stdio_body() {
+        let capture = Arc::new(CaptureLogger::new());
+        let redirect = redirect_stdio(capture.clone()).unwrap();
+        assert_eq!(
+            redirect_stdio(CaptureLogger::new()).err().unwrap().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        // The test harness captures the output of print!() and eprint!(), so we write to the
+        // standard streams directly.
+        writeln!(io::stdout(), "Hello from stdout").unwrap();
+        write!(io::stderr(), "Hello from stderr\nwithout newline").unwrap();
+        let raw = b"Hello from the raw file descriptor\n";
+        unsafe { libc::write(libc::STDOUT_FILENO, raw.as_ptr() as *const _, raw.len()) };
+        write!(io::stdout(), "Buffered").unwrap();
+
+        redirect.stop().unwrap();
+
+        assert!(capture.contains(Level::Info, "Hello from stdout"));
+        assert!(capture.contains(Level::Info, "Hello from the raw file descriptor"));
+        assert!(capture.contains(Level::Info, "Buffered"));
+        assert!(capture.contains(Level::Warn, "Hello from stderr"));
+        assert!(capture.contains(Level::Warn, "without newline"));
+
+        // Stdout is restored.
+        let count = capture.records().len();
+        writeln!(io::stdout(), "Not captured").unwrap();
+        assert_eq!(capture.records().len(), count);
+
+        // Another redirection can be created afterwards.
+        redirect_stdio(CaptureLogger::new())
+            .unwrap()
+            .stop()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_redirect_stdio_stop_with_dup() {
+        run_in_subprocess(
+            "logger::redirect::tests::test_redirect_stdio_stop_with_dup",
+            || {
+                let capture = Arc::new(CaptureLogger::new());
+                let redirect = redirect_stdio(capture.clone()).unwrap();
+                // Another copy of the write end of the pipe remains open after stop().
+                let dup = check(unsafe { libc::dup(libc::STDOUT_FILENO) }).unwrap();
+                let line = b"Written to the dup\n";
+                unsafe { libc::write(dup, line.as_ptr() as *const _, line.len()) };
+
+                redirect.stop().unwrap();
+                assert!(capture.contains(Level::Info, "Written to the dup"));
+                unsafe { libc::close(dup) };
+            },
+        );
+    }
+}
@@ -1,43 +1,82 @@
-pub fn pmul_strategy(a: u64, b: u64) -> (u128, &'static str) {
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    {
-        if is_x86_feature_detected!("sse2") && is_x86_feature_detected!("pclmulqdq") {
-            // Safety: target_features "sse2" and "pclmulqdq" are available in this block.
-            return unsafe { pmul_strategy_x86_clmul(a, b) };
-        }
+use crate::dispatch::dispatch;
+use crate::selftest::DisabledStrategies;
+
+/// Strategies that failed a self-test.
+static DISABLED: DisabledStrategies = DisabledStrategies::new();
+
+dispatch! {
+    pub fn pmul_strategy(a: u64, b: u64) -> (u128, &'static str) {
+        x86["sse2", "pclmulqdq"] if !PmulStrategy::X86Clmul.is_disabled()
+            => unsafe pmul_strategy_x86_clmul,
+        aarch64["neon", "aes"] if !PmulStrategy::Aarch64Neon.is_disabled()
+            => unsafe pmul_strategy_aarch64_neon,
+        // "pmull" is part of the ARMv8 crypto extension, which implies the "v8" and "aes" features.
+        arm["neon", "pmull"] if !PmulStrategy::ArmPmull.is_disabled()
+            => unsafe pmul_strategy_arm_pmull,
+        arm["neon"] if !PmulStrategy::ArmNeon.is_disabled() => unsafe pmul_strategy_arm_neon,
+        _ => pmul_strategy_nosimd,
     }
-    #[cfg(target_arch = "aarch64")]
-    {
-        use std::arch::is_aarch64_feature_detected;
-        if is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes") {
-            // Safety: target_features "neon" and "aes" are available in this block.
-            return unsafe { pmul_strategy_aarch64_neon(a, b) };
-        }
+}
+
+dispatch! {
+    pub fn pmul_strategy_cheat(a: u64, b: u64) -> (u128, &'static str) {
+        x86["sse2", "pclmulqdq"] if !PmulStrategy::X86Clmul.is_disabled()
+            => unsafe pmul_strategy_x86_clmul,
+        // FIXME: Here we cheat and omit to detect the "aes" feature.
+        aarch64["neon"] if !PmulStrategy::Aarch64Neon.is_disabled()
+            => unsafe pmul_strategy_aarch64_neon,
+        // Unlike on aarch64, many armv7 CPUs lack the "pmull" feature, so we don't cheat here.
+        // "pmull" is part of the ARMv8 crypto extension, which implies the "v8" and "aes" features.
+        arm["neon", "pmull"] if !PmulStrategy::ArmPmull.is_disabled()
+            => unsafe pmul_strategy_arm_pmull,
+        arm["neon"] if !PmulStrategy::ArmNeon.is_disabled() => unsafe pmul_strategy_arm_neon,
+        _ => pmul_strategy_nosimd,
     }
-    pmul_strategy_nosimd(a, b)
 }
 
-pub fn pmul_strategy_cheat(a: u64, b: u64) -> (u128, &'static str) {
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    {
-        if is_x86_feature_detected!("sse2") && is_x86_feature_detected!("pclmulqdq") {
-            // Safety: target_features "sse2" and "pclmulqdq" are available in this block.
-            return unsafe { pmul_strategy_x86_clmul(a, b) };
-        }
+/// Masks of the bits whose position is congruent to 0, 1, 2, 3 and 4 modulo 5.
+const SPACED_MASKS: [u128; 5] = [
+    spaced_mask(0),
+    spaced_mask(1),
+    spaced_mask(2),
+    spaced_mask(3),
+    spaced_mask(4),
+];
+
+const fn spaced_mask(offset: u32) -> u128 {
+    let mut mask = 0;
+    let mut i = offset;
+    while i < 128 {
+        mask |= 1 << i;
+        i += 5;
     }
-    #[cfg(target_arch = "aarch64")]
-    {
-        use std::arch::is_aarch64_feature_detected;
-        // FIXME: Here we cheat and omit to detect the "aes" feature.
-        if is_aarch64_feature_detected!("neon") {
-            // Safety: target_features "neon" and "aes" are available in this block.
-            return unsafe { pmul_strategy_aarch64_neon(a, b) };
+    mask
+}
+
+/// Constant-time implementation, with integer multiplications of operands whose bits are spread
+/// apart by "holes" of zeros, following BearSSL (https://www.bearssl.org/constanttime.html).
+///
+/// Each operand is split into 5 parts that keep every 5th bit. In the integer product of two
+/// parts, each bit position receives at most 13 terms, so the carries stay within the 4 bits of
+/// the hole, and the positions congruent to the sum of the offsets hold the parities of the
+/// carry-less product. This assumes that the 64x64-bit integer multiplication runs in constant
+/// time, which is the case on aarch64 and x86_64 CPUs.
+pub fn pmul_strategy_nosimd(a: u64, b: u64) -> (u128, &'static str) {
+    let mut result: u128 = 0;
+    for i in 0..5 {
+        let x = (a & SPACED_MASKS[i] as u64) as u128;
+        for j in 0..5 {
+            let y = (b & SPACED_MASKS[j] as u64) as u128;
+            result ^= (x * y) & SPACED_MASKS[(i + j) % 5];
         }
     }
-    pmul_strategy_nosimd(a, b)
+    (result, "nosimd")
 }
 
-pub fn pmul_strategy_nosimd(a: u64, b: u64) -> (u128, &'static str) {
+/// Straightforward implementation, kept as a reference. It isn't constant-time: it branches on
+/// each bit of `a`.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn pmul_strategy_nosimd_reference(a: u64, b: u64) -> (u128, &'static str) {
     let mut tmp: u128 = b as u128;
     let mut result: u128 = 0;
     for i in 0..64 {
@@ -46,12 +85,12 @@ pub fn pmul_strategy_nosimd(a: u64, b: u64) -> (u128, &'static str) {
         }
         tmp <<= 1;
     }
-    (result, "nosimd")
+    (result, "nosimd_reference")
 }
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[target_feature(enable = "sse2", enable = "pclmulqdq")]
-unsafe fn pmul_strategy_x86_clmul(a: u64, b: u64) -> (u128, &'static str) {
+pub(crate) unsafe fn pmul_strategy_x86_clmul(a: u64, b: u64) -> (u128, &'static str) {
     #[cfg(target_arch = "x86")]
     use std::arch::x86::{__m128i, _mm_clmulepi64_si128, _mm_set_epi64x, _mm_storeu_si128};
     #[cfg(target_arch = "x86_64")]
@@ -75,7 +114,7 @@ unsafe fn pmul_strategy_x86_clmul(a: u64, b: u64) -> (u128, &'static str) {
 
 #[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "neon", enable = "aes")]
-unsafe fn pmul_strategy_aarch64_neon(a: u64, b: u64) -> (u128, &'static str) {
+pub(crate) unsafe fn pmul_strategy_aarch64_neon(a: u64, b: u64) -> (u128, &'static str) {
     use std::arch::aarch64::vmull_p64;
 
     // Safety: target_features "neon" and "aes" are available in this function.
@@ -83,58 +122,542 @@ unsafe fn pmul_strategy_aarch64_neon(a: u64, b: u64) -> (u128, &'static str) {
     (result, "aarch64_neon")
 }
 
-#[cfg(test)]
-pub fn pmul(a: u64, b: u64) -> u128 {
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    {
-        if is_x86_feature_detected!("sse2") && is_x86_feature_detected!("pclmulqdq") {
-            // Safety: target_features "sse2" and "pclmulqdq" are available in this block.
-            return unsafe { pmul_x86_clmul(a, b) };
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v8", enable = "neon", enable = "aes")]
+unsafe fn pmul_strategy_arm_pmull(a: u64, b: u64) -> (u128, &'static str) {
+    use std::arch::arm::vmull_p64;
+
+    // Safety: target_features "v8", "neon" and "aes" are available in this function.
+    let result: u128 = vmull_p64(a, b);
+    (result, "arm_pmull")
+}
+
+/// Builds the 64x64-bit product from 8x8-bit VMULL.P8 products, following Câmara et al., "Fast
+/// Software Polynomial Multiplication on ARM Processors Using the NEON Engine" (2013).
+///
+/// Each VMULL.P8 multiplies the bytes of a and b at the same index, after rotating one of them by
+/// 0 to 4 bytes, which covers all 64 pairs of bytes with 8 multiplications. The 16-bit products
+/// are then moved to their byte offset in the 128-bit result.
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v7", enable = "neon")]
+pub(crate) unsafe fn pmul_strategy_arm_neon(a: u64, b: u64) -> (u128, &'static str) {
+    use std::arch::arm::{
+        poly8x8_t, uint64x2_t, uint8x16_t, vcreate_p8, veorq_u64, veorq_u8, vext_p8, vextq_u8,
+        vmull_p8, vreinterpretq_u64_p16, vreinterpretq_u8_u64,
+    };
+    use std::mem::transmute;
+
+    // Safety: target_features "v7" and "neon" are available in this function.
+    let a: poly8x8_t = vcreate_p8(a);
+    let b: poly8x8_t = vcreate_p8(b);
+
+    // Products of the bytes whose indices differ by 0, 1, 2, 3 and 4 (modulo 8).
+    let t0: uint64x2_t = vreinterpretq_u64_p16(vmull_p8(a, b));
+    let t1: uint64x2_t = veorq_u64(
+        vreinterpretq_u64_p16(vmull_p8(vext_p8(a, a, 1), b)),
+        vreinterpretq_u64_p16(vmull_p8(a, vext_p8(b, b, 1))),
+    );
+    let t2: uint64x2_t = veorq_u64(
+        vreinterpretq_u64_p16(vmull_p8(vext_p8(a, a, 2), b)),
+        vreinterpretq_u64_p16(vmull_p8(a, vext_p8(b, b, 2))),
+    );
+    let t3: uint64x2_t = veorq_u64(
+        vreinterpretq_u64_p16(vmull_p8(vext_p8(a, a, 3), b)),
+        vreinterpretq_u64_p16(vmull_p8(a, vext_p8(b, b, 3))),
+    );
+    let t4: uint64x2_t = vreinterpretq_u64_p16(vmull_p8(a, vext_p8(b, b, 4)));
+
+    // Shift each sum left by its index difference, in bytes.
+    let t1: uint8x16_t = fold_wrapped_arm_neon(t1, 0x0000_ffff_ffff_ffff);
+    let t2: uint8x16_t = fold_wrapped_arm_neon(t2, 0x0000_0000_ffff_ffff);
+    let t3: uint8x16_t = fold_wrapped_arm_neon(t3, 0x0000_0000_0000_ffff);
+    let t4: uint8x16_t = fold_wrapped_arm_neon(t4, 0);
+    let result: uint8x16_t = veorq_u8(
+        veorq_u8(vreinterpretq_u8_u64(t0), vextq_u8(t1, t1, 15)),
+        veorq_u8(
+            veorq_u8(vextq_u8(t2, t2, 14), vextq_u8(t3, t3, 13)),
+            vextq_u8(t4, t4, 12),
+        ),
+    );
+
+    // Safety: uint8x16_t and u128 have the same size, and any bits are valid for both.
+    (transmute(result), "arm_neon")
+}
+
+/// Prepares the products of bytes whose indices differ by `n` for a rotation left by `n` bytes.
+///
+/// The products whose indices wrapped around modulo 8 belong 8 bytes lower than the others. They
+/// are the 16-bit lanes of the high half that `mask` clears, and are moved to the low half, so
+/// that the rotation puts them in place instead of wrapping them around the 128-bit result.
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v7", enable = "neon")]
+#[inline]
+unsafe fn fold_wrapped_arm_neon(
+    t: std::arch::arm::uint64x2_t,
+    mask: u64,
+) -> std::arch::arm::uint8x16_t {
+    use std::arch::arm::{
+        uint64x1_t, vand_u64, vcombine_u64, vcreate_u64, veor_u64, vget_high_u64, vget_low_u64,
+        vreinterpretq_u8_u64,
+    };
+
+    // Safety: target_features "v7" and "neon" are available in this function.
+    let low: uint64x1_t = vget_low_u64(t);
+    let high: uint64x1_t = vget_high_u64(t);
+    let kept: uint64x1_t = vand_u64(high, vcreate_u64(mask));
+    let low: uint64x1_t = veor_u64(low, veor_u64(high, kept));
+    vreinterpretq_u8_u64(vcombine_u64(low, kept))
+}
+
+/// Implementation of the polynomial multiplication.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PmulStrategy {
+    /// Portable implementation, available on all CPUs.
+    Nosimd,
+    /// PCLMULQDQ instruction, on x86 and x86_64 CPUs with the "sse2" and "pclmulqdq" features.
+    X86Clmul,
+    /// PMULL instruction, on aarch64 CPUs with the "neon" and "aes" features.
+    Aarch64Neon,
+    /// 8x8-bit VMULL.P8 instructions, on arm CPUs with the "neon" feature.
+    ArmNeon,
+    /// 64x64-bit VMULL.P64 instruction, on arm CPUs with the "neon" and "pmull" features.
+    ArmPmull,
+    /// VPCLMULQDQ instruction on 256-bit registers, on x86 and x86_64 CPUs with the "avx2" and
+    /// "vpclmulqdq" features (as well as the features of "x86_clmul"). Only the batched
+    /// multiplication uses the wide registers, the other operations are the same as "x86_clmul".
+    X86VpclmulAvx2,
+    /// VPCLMULQDQ instruction on 512-bit registers, on x86 and x86_64 CPUs with the "avx512f" and
+    /// "vpclmulqdq" features (as well as the features of "x86_clmul"). Only the batched
+    /// multiplication uses the wide registers, the other operations are the same as "x86_clmul".
+    X86VpclmulAvx512,
+}
+
+/// Error returned when a strategy isn't supported by the current CPU.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Unsupported(pub PmulStrategy);
+
+impl PmulStrategy {
+    /// All the strategies, whether they are supported by the current CPU or not.
+    pub const ALL: [PmulStrategy; 7] = [
+        PmulStrategy::Nosimd,
+        PmulStrategy::X86Clmul,
+        PmulStrategy::Aarch64Neon,
+        PmulStrategy::ArmNeon,
+        PmulStrategy::ArmPmull,
+        PmulStrategy::X86VpclmulAvx2,
+        PmulStrategy::X86VpclmulAvx512,
+    ];
+
+    /// Name of the strategy, as returned by the `pmul_strategy*()` functions.
+    pub fn name(self) -> &'static str {
+        match self {
+            PmulStrategy::Nosimd => "nosimd",
+            PmulStrategy::X86Clmul => "x86_clmul",
+            PmulStrategy::Aarch64Neon => "aarch64_neon",
+            PmulStrategy::ArmNeon => "arm_neon",
+            PmulStrategy::ArmPmull => "arm_pmull",
+            PmulStrategy::X86VpclmulAvx2 => "x86_vpclmul_avx2",
+            PmulStrategy::X86VpclmulAvx512 => "x86_vpclmul_avx512",
         }
     }
-    #[cfg(target_arch = "aarch64")]
-    {
-        use std::arch::is_aarch64_feature_detected;
-        if is_aarch64_feature_detected!("neon") && is_aarch64_feature_detected!("aes") {
-            // Safety: target_features "neon" and "aes" are available in this block.
-            return unsafe { pmul_aarch64_neon(a, b) };
+
+    /// Returns whether the current CPU supports this strategy.
+    pub fn is_available(self) -> bool {
+        match self {
+            PmulStrategy::Nosimd => true,
+            PmulStrategy::X86Clmul => {
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                return is_x86_feature_detected!("sse2") && is_x86_feature_detected!("pclmulqdq");
+                #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+                return false;
+            }
+            PmulStrategy::Aarch64Neon => {
+                #[cfg(target_arch = "aarch64")]
+                {
+                    use std::arch::is_aarch64_feature_detected;
+                    return is_aarch64_feature_detected!("neon")
+                        && is_aarch64_feature_detected!("aes");
+                }
+                #[cfg(not(target_arch = "aarch64"))]
+                return false;
+            }
+            PmulStrategy::ArmNeon => {
+                #[cfg(target_arch = "arm")]
+                {
+                    use std::arch::is_arm_feature_detected;
+                    return is_arm_feature_detected!("neon");
+                }
+                #[cfg(not(target_arch = "arm"))]
+                return false;
+            }
+            PmulStrategy::ArmPmull => {
+                #[cfg(target_arch = "arm")]
+                {
+                    use std::arch::is_arm_feature_detected;
+                    return is_arm_feature_detected!("neon") && is_arm_feature_detected!("pmull");
+                }
+                #[cfg(not(target_arch = "arm"))]
+                return false;
+            }
+            PmulStrategy::X86VpclmulAvx2 => {
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                return PmulStrategy::X86Clmul.is_available()
+                    && is_x86_feature_detected!("avx2")
+                    && is_x86_feature_detected!("vpclmulqdq");
+                #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+                return false;
+            }
+            PmulStrategy::X86VpclmulAvx512 => {
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                return PmulStrategy::X86Clmul.is_available()
+                    && is_x86_feature_detected!("avx512f")
+                    && is_x86_feature_detected!("vpclmulqdq");
+                #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+                return false;
+            }
         }
     }
-    pmul_nosimd(a, b)
+
+    /// Marks this strategy as unusable, so that `pmul_strategy()` and `pmul_strategy_cheat()` avoid
+    /// it. The "nosimd" strategy remains their last resort, and `pmul_with()` still runs disabled
+    /// strategies when explicitly asked to.
+    pub fn disable(self) {
+        DISABLED.disable(self as usize);
+    }
+
+    /// Returns whether this strategy was disabled.
+    pub fn is_disabled(self) -> bool {
+        DISABLED.is_disabled(self as usize)
+    }
 }
 
-#[cfg(test)]
-pub fn pmul_cheat(a: u64, b: u64) -> u128 {
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    {
-        if is_x86_feature_detected!("sse2") && is_x86_feature_detected!("pclmulqdq") {
-            // Safety: target_features "sse2" and "pclmulqdq" are available in this block.
-            return unsafe { pmul_x86_clmul(a, b) };
+/// Returns the strategies supported by the current CPU.
+pub fn available_strategies() -> Vec<PmulStrategy> {
+    PmulStrategy::ALL
+        .into_iter()
+        .filter(|strategy| strategy.is_available())
+        .collect()
+}
+
+/// Returns the strategy that `pmul_strategy()` selects: the first hardware one that the current
+/// CPU supports and that wasn't disabled, and "nosimd" otherwise. On arm, "arm_pmull" is
+/// preferred over "arm_neon".
+pub fn preferred_strategy() -> PmulStrategy {
+    [
+        PmulStrategy::X86Clmul,
+        PmulStrategy::Aarch64Neon,
+        PmulStrategy::ArmPmull,
+        PmulStrategy::ArmNeon,
+    ]
+    .into_iter()
+    .find(|strategy| strategy.is_available() && !strategy.is_disabled())
+    .unwrap_or(PmulStrategy::Nosimd)
+}
+
+/// Computes the polynomial multiplication with the given strategy, if the current CPU supports
+/// it.
+pub fn pmul_with(strategy: PmulStrategy, a: u64, b: u64) -> Result<u128, Unsupported> {
+    if !strategy.is_available() {
+        return Err(Unsupported(strategy));
+    }
+    match strategy {
+        PmulStrategy::Nosimd => Ok(pmul_strategy_nosimd(a, b).0),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        // Safety: target_features "sse2" and "pclmulqdq" are available, as checked above.
+        PmulStrategy::X86Clmul | PmulStrategy::X86VpclmulAvx2 | PmulStrategy::X86VpclmulAvx512 => {
+            Ok(unsafe { pmul_strategy_x86_clmul(a, b) }.0)
         }
+        #[cfg(target_arch = "aarch64")]
+        // Safety: target_features "neon" and "aes" are available, as checked above.
+        PmulStrategy::Aarch64Neon => Ok(unsafe { pmul_strategy_aarch64_neon(a, b) }.0),
+        #[cfg(target_arch = "arm")]
+        // Safety: target_features "v7" and "neon" are available, as checked above.
+        PmulStrategy::ArmNeon => Ok(unsafe { pmul_strategy_arm_neon(a, b) }.0),
+        #[cfg(target_arch = "arm")]
+        // Safety: target_features "v8", "neon" and "aes" are available, as checked above.
+        PmulStrategy::ArmPmull => Ok(unsafe { pmul_strategy_arm_pmull(a, b) }.0),
+        #[allow(unreachable_patterns)]
+        _ => Err(Unsupported(strategy)),
     }
-    #[cfg(target_arch = "aarch64")]
-    {
-        use std::arch::is_aarch64_feature_detected;
-        // FIXME: Here we cheat and omit to detect the "aes" feature.
-        if is_aarch64_feature_detected!("neon") {
-            // Safety: target_features "neon" and "aes" are available in this block.
-            return unsafe { pmul_aarch64_neon(a, b) };
+}
+
+/// Combines the three 64x64-bit products of Karatsuba's method into a 128x128-bit product, as
+/// `[low, high]` halves.
+#[inline(always)]
+fn karatsuba128(lo: u128, hi: u128, mid: u128) -> [u128; 2] {
+    let mid = mid ^ lo ^ hi;
+    [lo ^ (mid << 64), hi ^ (mid >> 64)]
+}
+
+/// Computes a 256x256-bit product with Karatsuba's method, given a 128x128-bit multiplication.
+#[inline(always)]
+fn karatsuba256(a: [u128; 2], b: [u128; 2], mul: impl Fn(u128, u128) -> [u128; 2]) -> [u128; 4] {
+    let lo = mul(a[0], b[0]);
+    let hi = mul(a[1], b[1]);
+    let mid = mul(a[0] ^ a[1], b[0] ^ b[1]);
+    let mid = [mid[0] ^ lo[0] ^ hi[0], mid[1] ^ lo[1] ^ hi[1]];
+    [lo[0], lo[1] ^ mid[0], hi[0] ^ mid[1], hi[1]]
+}
+
+dispatch! {
+    /// Carry-less multiplication of 128-bit polynomials, returning the 256-bit product as
+    /// `[low, high]` halves. The strategy is selected in the same way as `pmul_strategy()`.
+    pub fn pmul128_strategy(a: u128, b: u128) -> ([u128; 2], &'static str) {
+        x86["sse2", "pclmulqdq"] if !PmulStrategy::X86Clmul.is_disabled()
+            => unsafe pmul128_strategy_x86_clmul,
+        aarch64["neon", "aes"] if !PmulStrategy::Aarch64Neon.is_disabled()
+            => unsafe pmul128_strategy_aarch64_neon,
+        // "pmull" is part of the ARMv8 crypto extension, which implies the "v8" and "aes" features.
+        arm["neon", "pmull"] if !PmulStrategy::ArmPmull.is_disabled()
+            => unsafe pmul128_strategy_arm_pmull,
+        arm["neon"] if !PmulStrategy::ArmNeon.is_disabled() => unsafe pmul128_strategy_arm_neon,
+        _ => pmul128_strategy_nosimd,
+    }
+}
+
+pub fn pmul128_strategy_nosimd(a: u128, b: u128) -> ([u128; 2], &'static str) {
+    let (a0, a1) = (a as u64, (a >> 64) as u64);
+    let (b0, b1) = (b as u64, (b >> 64) as u64);
+    let (lo, _) = pmul_strategy_nosimd(a0, b0);
+    let (hi, _) = pmul_strategy_nosimd(a1, b1);
+    let (mid, _) = pmul_strategy_nosimd(a0 ^ a1, b0 ^ b1);
+    (karatsuba128(lo, hi, mid), "nosimd")
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2", enable = "pclmulqdq")]
+unsafe fn pmul128_strategy_x86_clmul(a: u128, b: u128) -> ([u128; 2], &'static str) {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{
+        __m128i, _mm_clmulepi64_si128, _mm_loadu_si128, _mm_srli_si128, _mm_storeu_si128,
+        _mm_xor_si128,
+    };
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{
+        __m128i, _mm_clmulepi64_si128, _mm_loadu_si128, _mm_srli_si128, _mm_storeu_si128,
+        _mm_xor_si128,
+    };
+
+    // Safety:
+    // - target_feature "sse2" is available in this function,
+    // - a and b point to 128 bits (no alignment required by this function).
+    let x: __m128i = _mm_loadu_si128(&a as *const _ as *const __m128i);
+    let y: __m128i = _mm_loadu_si128(&b as *const _ as *const __m128i);
+    // Safety: target_feature "pclmulqdq" is available in this function.
+    let lo: __m128i = _mm_clmulepi64_si128(x, y, 0x00);
+    let hi: __m128i = _mm_clmulepi64_si128(x, y, 0x11);
+    // Safety: target_feature "sse2" is available in this function.
+    let x_sum: __m128i = _mm_xor_si128(x, _mm_srli_si128(x, 8));
+    let y_sum: __m128i = _mm_xor_si128(y, _mm_srli_si128(y, 8));
+    // Safety: target_feature "pclmulqdq" is available in this function.
+    let mid: __m128i = _mm_clmulepi64_si128(x_sum, y_sum, 0x00);
+
+    let mut products: [u128; 3] = [0; 3];
+    // Safety:
+    // - target_feature "sse2" is available in this function,
+    // - each element of products points to 128 bits (no alignment required by this function).
+    _mm_storeu_si128(&mut products[0] as *mut _ as *mut __m128i, lo);
+    _mm_storeu_si128(&mut products[1] as *mut _ as *mut __m128i, hi);
+    _mm_storeu_si128(&mut products[2] as *mut _ as *mut __m128i, mid);
+
+    let [lo, hi, mid] = products;
+    (karatsuba128(lo, hi, mid), "x86_clmul")
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon", enable = "aes")]
+unsafe fn pmul128_strategy_aarch64_neon(a: u128, b: u128) -> ([u128; 2], &'static str) {
+    use std::arch::aarch64::vmull_p64;
+
+    let (a0, a1) = (a as u64, (a >> 64) as u64);
+    let (b0, b1) = (b as u64, (b >> 64) as u64);
+    // Safety: target_features "neon" and "aes" are available in this function.
+    let lo: u128 = vmull_p64(a0, b0);
+    let hi: u128 = vmull_p64(a1, b1);
+    let mid: u128 = vmull_p64(a0 ^ a1, b0 ^ b1);
+    (karatsuba128(lo, hi, mid), "aarch64_neon")
+}
+
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v8", enable = "neon", enable = "aes")]
+unsafe fn pmul128_strategy_arm_pmull(a: u128, b: u128) -> ([u128; 2], &'static str) {
+    use std::arch::arm::vmull_p64;
+
+    let (a0, a1) = (a as u64, (a >> 64) as u64);
+    let (b0, b1) = (b as u64, (b >> 64) as u64);
+    // Safety: target_features "v8", "neon" and "aes" are available in this function.
+    let lo: u128 = vmull_p64(a0, b0);
+    let hi: u128 = vmull_p64(a1, b1);
+    let mid: u128 = vmull_p64(a0 ^ a1, b0 ^ b1);
+    (karatsuba128(lo, hi, mid), "arm_pmull")
+}
+
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v7", enable = "neon")]
+unsafe fn pmul128_strategy_arm_neon(a: u128, b: u128) -> ([u128; 2], &'static str) {
+    let (a0, a1) = (a as u64, (a >> 64) as u64);
+    let (b0, b1) = (b as u64, (b >> 64) as u64);
+    // Safety: target_features "v7" and "neon" are available in this function.
+    let (lo, _) = pmul_strategy_arm_neon(a0, b0);
+    let (hi, _) = pmul_strategy_arm_neon(a1, b1);
+    let (mid, _) = pmul_strategy_arm_neon(a0 ^ a1, b0 ^ b1);
+    (karatsuba128(lo, hi, mid), "arm_neon")
+}
+
+/// Computes the carry-less multiplication of 128-bit polynomials with the given strategy, if the
+/// current CPU supports it.
+pub fn pmul128_with(strategy: PmulStrategy, a: u128, b: u128) -> Result<[u128; 2], Unsupported> {
+    Ok(Pmul128::new(strategy)?.mul(a, b))
+}
+
+/// Carry-less multiplication of 128-bit polynomials with a strategy that the current CPU supports,
+/// whose implementation is resolved once. Callers that compute many products, such as the
+/// universal hashes, avoid checking and matching the strategy for each of them.
+#[derive(Clone, Copy, Debug)]
+pub struct Pmul128 {
+    strategy: PmulStrategy,
+    function: unsafe fn(u128, u128) -> ([u128; 2], &'static str),
+}
+
+impl Pmul128 {
+    /// Resolves the implementation of the given strategy, if the current CPU supports it.
+    pub fn new(strategy: PmulStrategy) -> Result<Self, Unsupported> {
+        if !strategy.is_available() {
+            return Err(Unsupported(strategy));
         }
+        let function: unsafe fn(u128, u128) -> ([u128; 2], &'static str) = match strategy {
+            PmulStrategy::Nosimd => pmul128_strategy_nosimd,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            PmulStrategy::X86Clmul
+            | PmulStrategy::X86VpclmulAvx2
+            | PmulStrategy::X86VpclmulAvx512 => pmul128_strategy_x86_clmul,
+            #[cfg(target_arch = "aarch64")]
+            PmulStrategy::Aarch64Neon => pmul128_strategy_aarch64_neon,
+            #[cfg(target_arch = "arm")]
+            PmulStrategy::ArmNeon => pmul128_strategy_arm_neon,
+            #[cfg(target_arch = "arm")]
+            PmulStrategy::ArmPmull => pmul128_strategy_arm_pmull,
+            #[allow(unreachable_patterns)]
+            _ => return Err(Unsupported(strategy)),
+        };
+        Ok(Pmul128 { strategy, function })
+    }
+
+    /// Returns the strategy of the multiplication.
+    pub fn strategy(self) -> PmulStrategy {
+        self.strategy
+    }
+
+    /// Returns the 256-bit product as `[low, high]` halves.
+    #[inline]
+    pub fn mul(self, a: u128, b: u128) -> [u128; 2] {
+        // Safety: the target features of the function are available, as checked by new().
+        unsafe { (self.function)(a, b) }.0
     }
-    pmul_nosimd(a, b)
 }
 
-#[cfg(test)]
-pub fn pmul_nosimd(a: u64, b: u64) -> u128 {
-    let mut tmp: u128 = b as u128;
-    let mut result: u128 = 0;
-    for i in 0..64 {
-        if a & (1 << i) != 0 {
-            result ^= tmp;
+dispatch! {
+    /// Carry-less multiplication of 256-bit polynomials, given and returned as 128-bit limbs from
+    /// the least to the most significant. The strategy is selected in the same way as
+    /// `pmul_strategy()`.
+    pub fn pmul256_strategy(a: [u128; 2], b: [u128; 2]) -> ([u128; 4], &'static str) {
+        x86["sse2", "pclmulqdq"] if !PmulStrategy::X86Clmul.is_disabled()
+            => unsafe pmul256_strategy_x86_clmul,
+        aarch64["neon", "aes"] if !PmulStrategy::Aarch64Neon.is_disabled()
+            => unsafe pmul256_strategy_aarch64_neon,
+        // "pmull" is part of the ARMv8 crypto extension, which implies the "v8" and "aes" features.
+        arm["neon", "pmull"] if !PmulStrategy::ArmPmull.is_disabled()
+            => unsafe pmul256_strategy_arm_pmull,
+        arm["neon"] if !PmulStrategy::ArmNeon.is_disabled() => unsafe pmul256_strategy_arm_neon,
+        _ => pmul256_strategy_nosimd,
+    }
+}
+
+pub fn pmul256_strategy_nosimd(a: [u128; 2], b: [u128; 2]) -> ([u128; 4], &'static str) {
+    let result = karatsuba256(a, b, |x, y| pmul128_strategy_nosimd(x, y).0);
+    (result, "nosimd")
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2", enable = "pclmulqdq")]
+unsafe fn pmul256_strategy_x86_clmul(a: [u128; 2], b: [u128; 2]) -> ([u128; 4], &'static str) {
+    // Safety: target_features "sse2" and "pclmulqdq" are available in this function.
+    let result = karatsuba256(a, b, |x, y| pmul128_strategy_x86_clmul(x, y).0);
+    (result, "x86_clmul")
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon", enable = "aes")]
+unsafe fn pmul256_strategy_aarch64_neon(a: [u128; 2], b: [u128; 2]) -> ([u128; 4], &'static str) {
+    // Safety: target_features "neon" and "aes" are available in this function.
+    let result = karatsuba256(a, b, |x, y| pmul128_strategy_aarch64_neon(x, y).0);
+    (result, "aarch64_neon")
+}
+
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v8", enable = "neon", enable = "aes")]
+unsafe fn pmul256_strategy_arm_pmull(a: [u128; 2], b: [u128; 2]) -> ([u128; 4], &'static str) {
+    // Safety: target_features "v8", "neon" and "aes" are available in this function.
+    let result = karatsuba256(a, b, |x, y| pmul128_strategy_arm_pmull(x, y).0);
+    (result, "arm_pmull")
+}
+
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v7", enable = "neon")]
+unsafe fn pmul256_strategy_arm_neon(a: [u128; 2], b: [u128; 2]) -> ([u128; 4], &'static str) {
+    // Safety: target_features "v7" and "neon" are available in this function.
+    let result = karatsuba256(a, b, |x, y| pmul128_strategy_arm_neon(x, y).0);
+    (result, "arm_neon")
+}
+
+/// Computes the carry-less multiplication of 256-bit polynomials with the given strategy, if the
+/// current CPU supports it.
+pub fn pmul256_with(
+    strategy: PmulStrategy,
+    a: [u128; 2],
+    b: [u128; 2],
+) -> Result<[u128; 4], Unsupported> {
+    if !strategy.is_available() {
+        return Err(Unsupported(strategy));
+    }
+    match strategy {
+        PmulStrategy::Nosimd => Ok(pmul256_strategy_nosimd(a, b).0),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        // Safety: target_features "sse2" and "pclmulqdq" are available, as checked above.
+        PmulStrategy::X86Clmul | PmulStrategy::X86VpclmulAvx2 | PmulStrategy::X86VpclmulAvx512 => {
+            Ok(unsafe { pmul256_strategy_x86_clmul(a, b) }.0)
         }
-        tmp <<= 1;
+        #[cfg(target_arch = "aarch64")]
+        // Safety: target_features "neon" and "aes" are available, as checked above.
+        PmulStrategy::Aarch64Neon => Ok(unsafe { pmul256_strategy_aarch64_neon(a, b) }.0),
+        #[cfg(target_arch = "arm")]
+        // Safety: target_features "v7" and "neon" are available, as checked above.
+        PmulStrategy::ArmNeon => Ok(unsafe { pmul256_strategy_arm_neon(a, b) }.0),
+        #[cfg(target_arch = "arm")]
+        // Safety: target_features "v8", "neon" and "aes" are available, as checked above.
+        PmulStrategy::ArmPmull => Ok(unsafe { pmul256_strategy_arm_pmull(a, b) }.0),
+        #[allow(unreachable_patterns)]
+        _ => Err(Unsupported(strategy)),
+    }
+}
+
+dispatch! {
+    #[cfg(test)]
+    pub fn pmul(a: u64, b: u64) -> u128 {
+        x86["sse2", "pclmulqdq"] => unsafe pmul_x86_clmul,
+        aarch64["neon", "aes"] => unsafe pmul_aarch64_neon,
+        _ => |a: u64, b: u64| pmul_strategy_nosimd_reference(a, b).0,
+    }
+}
+
+dispatch! {
+    #[cfg(test)]
+    pub fn pmul_cheat(a: u64, b: u64) -> u128 {
+        x86["sse2", "pclmulqdq"] => unsafe pmul_x86_clmul,
+        // FIXME: Here we cheat and omit to detect the "aes" feature.
+        aarch64["neon"] => unsafe pmul_aarch64_neon,
+        _ => |a: u64, b: u64| pmul_strategy_nosimd_reference(a, b).0,
     }
-    result
 }
 
 #[cfg(test)]
@@ -175,6 +698,7 @@ unsafe fn pmul_aarch64_neon(a: u64, b: u64) -> u128 {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::testutil::{bench_strategy, random_u64s, RANDOM_SEED};
     use std::hint::black_box;
     use test::Bencher;
 
@@ -201,6 +725,186 @@ pub mod tests {
         assert_eq!(strategy, "nosimd");
     }
 
+    #[test]
+    fn test_available_strategies() {
+        let strategies = available_strategies();
+        assert!(strategies.contains(&PmulStrategy::Nosimd));
+        #[cfg(target_arch = "aarch64")]
+        assert!(strategies.contains(&PmulStrategy::Aarch64Neon));
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        assert!(!strategies.contains(&PmulStrategy::X86Clmul));
+        #[cfg(not(target_arch = "aarch64"))]
+        assert!(!strategies.contains(&PmulStrategy::Aarch64Neon));
+        #[cfg(not(target_arch = "arm"))]
+        assert!(!strategies.contains(&PmulStrategy::ArmNeon));
+        #[cfg(not(target_arch = "arm"))]
+        assert!(!strategies.contains(&PmulStrategy::ArmPmull));
+    }
+
+    #[test]
+    fn test_preferred_strategy() {
+        let (_, name) = pmul_strategy(0x1234567890abcdef, 0xfedcba0987654321);
+        assert_eq!(preferred_strategy().name(), name);
+    }
+
+    #[test]
+    fn test_pmul_with() {
+        for strategy in PmulStrategy::ALL {
+            match pmul_with(strategy, 0x1234567890abcdef, 0xfedcba0987654321) {
+                Ok(result) => {
+                    assert!(strategy.is_available());
+                    assert_eq!(result, 0x0e038d8eab3af47a1f31f87ebb8c810f);
+                }
+                Err(e) => {
+                    assert!(!strategy.is_available());
+                    assert_eq!(e, Unsupported(strategy));
+                }
+            }
+        }
+    }
+
+    const A128: u128 = 0x0123456789abcdeffedcba9876543210;
+    const B128: u128 = 0xfedcba98765432100123456789abcdef;
+    const P256: [u128; 2] = [
+        0x55b56d8d3ddd05e540a0789828c810f0,
+        0x00e038d8688850b015f52dcd7d9d45a5,
+    ];
+
+    const A256: [u128; 2] = [
+        0x1234567890abcdeffedcba0987654321,
+        0x0123456789abcdeffedcba9876543210,
+    ];
+    const B256: [u128; 2] = [
+        0x0f1e2d3c4b5a69788796a5b4c3d2e1f0,
+        0xfedcba98765432100123456789abcdef,
+    ];
+    const P512: [u128; 4] = [
+        0x5e86abe6df9073b7bebc7ecc82e0cff0,
+        0x73285b7151d85e62d5afd8cb0a38dba1,
+        0x5bb80f37bce4a9f008104cb2af618561,
+        0x00e038d8688850b015f52dcd7d9d45a5,
+    ];
+
+    /// Schoolbook multiplication with 4 products, to cross-check Karatsuba's method.
+    fn pmul128_reference(a: u128, b: u128) -> [u128; 2] {
+        let (a0, a1) = (a as u64, (a >> 64) as u64);
+        let (b0, b1) = (b as u64, (b >> 64) as u64);
+        let pmul = |a, b| pmul_strategy_nosimd_reference(a, b).0;
+        let lo = pmul(a0, b0);
+        let hi = pmul(a1, b1);
+        let mid = pmul(a0, b1) ^ pmul(a1, b0);
+        [lo ^ (mid << 64), hi ^ (mid >> 64)]
+    }
+
+    fn pmul256_reference(a: [u128; 2], b: [u128; 2]) -> [u128; 4] {
+        let lo = pmul128_reference(a[0], b[0]);
+        let hi = pmul128_reference(a[1], b[1]);
+        let mid0 = pmul128_reference(a[0], b[1]);
+        let mid1 = pmul128_reference(a[1], b[0]);
+        let mid = [mid0[0] ^ mid1[0], mid0[1] ^ mid1[1]];
+        [lo[0], lo[1] ^ mid[0], hi[0] ^ mid[1], hi[1]]
+    }
+
+    fn random_u128s(count: usize) -> Vec<u128> {
+        random_u64s(2 * count, RANDOM_SEED)
+            .chunks(2)
+            .map(|words| (words[0] as u128) | ((words[1] as u128) << 64))
+            .collect()
+    }
+
+    #[test]
+    fn test_pmul128_strategy() {
+        let (result, _strategy) = pmul128_strategy(A128, B128);
+        assert_eq!(result, P256);
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(_strategy, "aarch64_neon");
+
+        assert_eq!(pmul128_strategy(A128, 1).0, [A128, 0]);
+        assert_eq!(pmul128_strategy(A128, 0).0, [0, 0]);
+        assert_eq!(pmul128_reference(A128, B128), P256);
+    }
+
+    #[test]
+    fn test_pmul256_strategy() {
+        let (result, _strategy) = pmul256_strategy(A256, B256);
+        assert_eq!(result, P512);
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(_strategy, "aarch64_neon");
+
+        assert_eq!(pmul256_strategy(A256, [1, 0]).0, [A256[0], A256[1], 0, 0]);
+        assert_eq!(pmul256_reference(A256, B256), P512);
+    }
+
+    #[test]
+    fn test_pmul128_cross_check() {
+        let inputs = random_u128s(64);
+        for strategy in PmulStrategy::ALL {
+            for pair in inputs.chunks(2) {
+                let (a, b) = (pair[0], pair[1]);
+                match pmul128_with(strategy, a, b) {
+                    Ok(result) => assert_eq!(result, pmul128_reference(a, b), "{strategy:?}"),
+                    Err(e) => assert_eq!(e, Unsupported(strategy)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pmul256_cross_check() {
+        let inputs = random_u128s(64);
+        for strategy in PmulStrategy::ALL {
+            for quad in inputs.chunks(4) {
+                let (a, b) = ([quad[0], quad[1]], [quad[2], quad[3]]);
+                match pmul256_with(strategy, a, b) {
+                    Ok(result) => assert_eq!(result, pmul256_reference(a, b), "{strategy:?}"),
+                    Err(e) => assert_eq!(e, Unsupported(strategy)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pmul_strategy_nosimd_reference() {
+        let (result, strategy) =
+            pmul_strategy_nosimd_reference(0x1234567890abcdef, 0xfedcba0987654321);
+        assert_eq!(result, 0x0e038d8eab3af47a1f31f87ebb8c810f);
+        assert_eq!(strategy, "nosimd_reference");
+    }
+
+    /// Pseudo-random pairs of 64-bit inputs, followed by pairs of edge cases.
+    fn random_pairs() -> Vec<(u64, u64)> {
+        let edge_cases = [0, 1, 0x8000000000000000, 0xffffffffffffffff];
+        random_u128s(256)
+            .into_iter()
+            .map(|x| (x as u64, (x >> 64) as u64))
+            .chain(edge_cases.iter().flat_map(|&a| edge_cases.map(|b| (a, b))))
+            .collect()
+    }
+
+    #[test]
+    fn test_pmul_strategy_nosimd_cross_check() {
+        for (a, b) in random_pairs() {
+            assert_eq!(
+                pmul_strategy_nosimd(a, b).0,
+                pmul_strategy_nosimd_reference(a, b).0,
+                "{a:016x} * {b:016x}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pmul_with_cross_check() {
+        for strategy in available_strategies() {
+            for (a, b) in random_pairs() {
+                assert_eq!(
+                    pmul_with(strategy, a, b),
+                    Ok(pmul_strategy_nosimd_reference(a, b).0),
+                    "{strategy:?}: {a:016x} * {b:016x}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_pmul() {
         let result = pmul(0x1234567890abcdef, 0xfedcba0987654321);
@@ -215,7 +919,7 @@ pub mod tests {
 
     #[test]
     fn test_pmul_nosimd() {
-        let result = pmul_nosimd(0x1234567890abcdef, 0xfedcba0987654321);
+        let (result, _) = pmul_strategy_nosimd_reference(0x1234567890abcdef, 0xfedcba0987654321);
         assert_eq!(result, 0x0e038d8eab3af47a1f31f87ebb8c810f);
     }
 
@@ -238,6 +942,16 @@ pub mod tests {
         });
     }
 
+    #[bench]
+    fn bench_pmul_strategy_nosimd_reference(b: &mut Bencher) {
+        b.iter(|| {
+            pmul_strategy_nosimd_reference(
+                black_box(0x1234567890abcdef),
+                black_box(0xfedcba0987654321),
+            )
+        });
+    }
+
     #[cfg(target_arch = "aarch64")]
     #[bench]
     fn bench_pmul_strategy_aarch64_neon(b: &mut Bencher) {
@@ -246,6 +960,77 @@ pub mod tests {
         });
     }
 
+    fn bench_pmul_with(b: &mut Bencher, strategy: PmulStrategy) {
+        bench_strategy(b, strategy.is_available(), |b| {
+            b.iter(|| {
+                pmul_with(
+                    strategy,
+                    black_box(0x1234567890abcdef),
+                    black_box(0xfedcba0987654321),
+                )
+            });
+        });
+    }
+
+    #[bench]
+    fn bench_pmul_with_nosimd(b: &mut Bencher) {
+        bench_pmul_with(b, PmulStrategy::Nosimd);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_pmul_with_x86_clmul(b: &mut Bencher) {
+        bench_pmul_with(b, PmulStrategy::X86Clmul);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[bench]
+    fn bench_pmul_with_aarch64_neon(b: &mut Bencher) {
+        bench_pmul_with(b, PmulStrategy::Aarch64Neon);
+    }
+
+    #[cfg(target_arch = "arm")]
+    #[bench]
+    fn bench_pmul_with_arm_neon(b: &mut Bencher) {
+        bench_pmul_with(b, PmulStrategy::ArmNeon);
+    }
+
+    #[cfg(target_arch = "arm")]
+    #[bench]
+    fn bench_pmul_with_arm_pmull(b: &mut Bencher) {
+        bench_pmul_with(b, PmulStrategy::ArmPmull);
+    }
+
+    #[bench]
+    fn bench_pmul128_strategy(b: &mut Bencher) {
+        b.iter(|| pmul128_strategy(black_box(A128), black_box(B128)));
+    }
+
+    #[bench]
+    fn bench_pmul128_strategy_nosimd(b: &mut Bencher) {
+        b.iter(|| pmul128_strategy_nosimd(black_box(A128), black_box(B128)));
+    }
+
+    #[bench]
+    fn bench_pmul128_reference(b: &mut Bencher) {
+        b.iter(|| pmul128_reference(black_box(A128), black_box(B128)));
+    }
+
+    #[bench]
+    fn bench_pmul256_strategy(b: &mut Bencher) {
+        b.iter(|| pmul256_strategy(black_box(A256), black_box(B256)));
+    }
+
+    #[bench]
+    fn bench_pmul256_strategy_nosimd(b: &mut Bencher) {
+        b.iter(|| pmul256_strategy_nosimd(black_box(A256), black_box(B256)));
+    }
+
+    #[bench]
+    fn bench_pmul256_reference(b: &mut Bencher) {
+        b.iter(|| pmul256_reference(black_box(A256), black_box(B256)));
+    }
+
     #[bench]
     fn bench_pmul(b: &mut Bencher) {
         b.iter(|| pmul(black_box(0x1234567890abcdef), black_box(0xfedcba0987654321)));
@@ -258,7 +1043,12 @@ pub mod tests {
 
     #[bench]
     fn bench_pmul_nosimd(b: &mut Bencher) {
-        b.iter(|| pmul_nosimd(black_box(0x1234567890abcdef), black_box(0xfedcba0987654321)));
+        b.iter(|| {
+            pmul_strategy_nosimd_reference(
+                black_box(0x1234567890abcdef),
+                black_box(0xfedcba0987654321),
+            )
+        });
     }
 
     #[cfg(target_arch = "aarch64")]
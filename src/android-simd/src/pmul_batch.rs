@@ -0,0 +1,514 @@
+//! Carry-less multiplication of slices of 64-bit polynomials.
+//!
+//! Each strategy computes several independent products per iteration, so that the latency of the
+//! PCLMULQDQ/PMULL instructions is hidden, and the strategy is selected once per slice rather than
+//! once per element. On x86 CPUs with VPCLMULQDQ, each instruction computes 2 or 4 products on
+//! 256-bit or 512-bit registers.
+
+#[cfg(target_arch = "arm")]
+use crate::pmul::pmul_strategy_arm_neon;
+use crate::pmul::{pmul_strategy_nosimd, preferred_strategy, PmulStrategy, Unsupported};
+
+/// Number of products computed per iteration.
+const LANES: usize = 4;
+
+/// Number of products computed per iteration on 256-bit and 512-bit registers.
+const WIDE_LANES: usize = 8;
+
+/// Returns the strategy that `pmul_batch()` selects: a VPCLMULQDQ strategy if the current CPU
+/// supports it and it wasn't disabled, and the same strategy as `pmul_strategy()` otherwise.
+pub fn preferred_batch_strategy() -> PmulStrategy {
+    [PmulStrategy::X86VpclmulAvx512, PmulStrategy::X86VpclmulAvx2]
+        .into_iter()
+        .find(|strategy| strategy.is_available() && !strategy.is_disabled())
+        .unwrap_or_else(preferred_strategy)
+}
+
+/// Computes `out[i] = a[i] * b[i]` for all `i`, and returns the name of the strategy. The strategy
+/// is selected by `preferred_batch_strategy()`.
+///
+/// Panics if the slices don't have the same length.
+pub fn pmul_batch(a: &[u64], b: &[u64], out: &mut [u128]) -> &'static str {
+    let strategy = preferred_batch_strategy();
+    pmul_batch_with(strategy, a, b, out).expect("Preferred strategy is unsupported");
+    strategy.name()
+}
+
+/// Computes `out[i] ^= a[i] * b[i]` for all `i`, and returns the name of the strategy. The
+/// strategy is selected by `preferred_batch_strategy()`.
+///
+/// Panics if the slices don't have the same length.
+pub fn pmul_batch_accumulate(a: &[u64], b: &[u64], out: &mut [u128]) -> &'static str {
+    let strategy = preferred_batch_strategy();
+    pmul_batch_accumulate_with(strategy, a, b, out).expect("Preferred strategy is unsupported");
+    strategy.name()
+}
+
+/// Computes `out[i] = a[i] * b[i]` for all `i` with the given strategy, if the current CPU
+/// supports it.
+///
+/// Panics if the slices don't have the same length.
+pub fn pmul_batch_with(
+    strategy: PmulStrategy,
+    a: &[u64],
+    b: &[u64],
+    out: &mut [u128],
+) -> Result<(), Unsupported> {
+    batch_with::<false>(strategy, a, b, out)
+}
+
+/// Computes `out[i] ^= a[i] * b[i]` for all `i` with the given strategy, if the current CPU
+/// supports it.
+///
+/// Panics if the slices don't have the same length.
+pub fn pmul_batch_accumulate_with(
+    strategy: PmulStrategy,
+    a: &[u64],
+    b: &[u64],
+    out: &mut [u128],
+) -> Result<(), Unsupported> {
+    batch_with::<true>(strategy, a, b, out)
+}
+
+fn batch_with<const ACCUMULATE: bool>(
+    strategy: PmulStrategy,
+    a: &[u64],
+    b: &[u64],
+    out: &mut [u128],
+) -> Result<(), Unsupported> {
+    assert_eq!(a.len(), b.len(), "Input slices have different lengths");
+    assert_eq!(a.len(), out.len(), "Output slice has a different length");
+    if !strategy.is_available() {
+        return Err(Unsupported(strategy));
+    }
+    match strategy {
+        PmulStrategy::Nosimd => batch::<ACCUMULATE, LANES>(a, b, out, kernel_nosimd),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        // Safety: target_features "sse2" and "pclmulqdq" are available, as checked above.
+        PmulStrategy::X86Clmul => {
+            batch::<ACCUMULATE, LANES>(a, b, out, |a, b| unsafe { kernel_x86_clmul(a, b) })
+        }
+        #[cfg(target_arch = "aarch64")]
+        // Safety: target_features "neon" and "aes" are available, as checked above.
+        PmulStrategy::Aarch64Neon => {
+            batch::<ACCUMULATE, LANES>(a, b, out, |a, b| unsafe { kernel_aarch64_neon(a, b) })
+        }
+        #[cfg(target_arch = "arm")]
+        // Safety: target_features "v7" and "neon" are available, as checked above.
+        PmulStrategy::ArmNeon => {
+            batch::<ACCUMULATE, LANES>(a, b, out, |a, b| unsafe { kernel_arm_neon(a, b) })
+        }
+        #[cfg(target_arch = "arm")]
+        // Safety: target_features "v8", "neon" and "aes" are available, as checked above.
+        PmulStrategy::ArmPmull => {
+            batch::<ACCUMULATE, LANES>(a, b, out, |a, b| unsafe { kernel_arm_pmull(a, b) })
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        // Safety: target_features "avx2" and "vpclmulqdq" are available, as checked above.
+        PmulStrategy::X86VpclmulAvx2 => batch::<ACCUMULATE, WIDE_LANES>(a, b, out, |a, b| unsafe {
+            kernel_x86_vpclmul_avx2(a, b)
+        }),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        // Safety: target_features "avx512f" and "vpclmulqdq" are available, as checked above.
+        PmulStrategy::X86VpclmulAvx512 => {
+            batch::<ACCUMULATE, WIDE_LANES>(a, b, out, |a, b| unsafe {
+                kernel_x86_vpclmul_avx512(a, b)
+            })
+        }
+        #[allow(unreachable_patterns)]
+        _ => return Err(Unsupported(strategy)),
+    }
+    Ok(())
+}
+
+/// Applies the kernel to each group of `N` lanes. The last group is padded with zeros.
+#[inline(always)]
+fn batch<const ACCUMULATE: bool, const N: usize>(
+    a: &[u64],
+    b: &[u64],
+    out: &mut [u128],
+    kernel: impl Fn(&[u64; N], &[u64; N]) -> [u128; N],
+) {
+    let mut a_chunks = a.chunks_exact(N);
+    let mut b_chunks = b.chunks_exact(N);
+    let mut out_chunks = out.chunks_exact_mut(N);
+    for ((a, b), out) in (&mut a_chunks).zip(&mut b_chunks).zip(&mut out_chunks) {
+        let products = kernel(a.try_into().unwrap(), b.try_into().unwrap());
+        store::<ACCUMULATE>(out, &products);
+    }
+
+    let remainder = a_chunks.remainder().len();
+    if remainder != 0 {
+        let mut a_padded = [0; N];
+        let mut b_padded = [0; N];
+        a_padded[..remainder].copy_from_slice(a_chunks.remainder());
+        b_padded[..remainder].copy_from_slice(b_chunks.remainder());
+        let products = kernel(&a_padded, &b_padded);
+        store::<ACCUMULATE>(out_chunks.into_remainder(), &products[..remainder]);
+    }
+}
+
+#[inline(always)]
+fn store<const ACCUMULATE: bool>(out: &mut [u128], products: &[u128]) {
+    for (out, product) in out.iter_mut().zip(products) {
+        if ACCUMULATE {
+            *out ^= product;
+        } else {
+            *out = *product;
+        }
+    }
+}
+
+/// Uses the constant-time implementation for each lane. The lanes are independent chains of
+/// integer multiplications, which the CPU can interleave.
+fn kernel_nosimd(a: &[u64; LANES], b: &[u64; LANES]) -> [u128; LANES] {
+    [
+        pmul_strategy_nosimd(a[0], b[0]).0,
+        pmul_strategy_nosimd(a[1], b[1]).0,
+        pmul_strategy_nosimd(a[2], b[2]).0,
+        pmul_strategy_nosimd(a[3], b[3]).0,
+    ]
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2", enable = "pclmulqdq")]
+unsafe fn kernel_x86_clmul(a: &[u64; LANES], b: &[u64; LANES]) -> [u128; LANES] {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{__m128i, _mm_clmulepi64_si128, _mm_loadu_si128, _mm_storeu_si128};
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{__m128i, _mm_clmulepi64_si128, _mm_loadu_si128, _mm_storeu_si128};
+
+    // Each register holds 2 lanes.
+    // Safety:
+    // - target_feature "sse2" is available in this function,
+    // - a and b point to 4 * 64 bits (no alignment required by this function).
+    let x0: __m128i = _mm_loadu_si128(a.as_ptr() as *const __m128i);
+    let x1: __m128i = _mm_loadu_si128(a.as_ptr().add(2) as *const __m128i);
+    let y0: __m128i = _mm_loadu_si128(b.as_ptr() as *const __m128i);
+    let y1: __m128i = _mm_loadu_si128(b.as_ptr().add(2) as *const __m128i);
+
+    // Safety: target_feature "pclmulqdq" is available in this function.
+    let p0: __m128i = _mm_clmulepi64_si128(x0, y0, 0x00);
+    let p1: __m128i = _mm_clmulepi64_si128(x0, y0, 0x11);
+    let p2: __m128i = _mm_clmulepi64_si128(x1, y1, 0x00);
+    let p3: __m128i = _mm_clmulepi64_si128(x1, y1, 0x11);
+
+    let mut result: [u128; LANES] = [0; LANES];
+    // Safety:
+    // - target_feature "sse2" is available in this function,
+    // - result points to 4 * 128 bits (no alignment required by this function).
+    let ptr = result.as_mut_ptr() as *mut __m128i;
+    _mm_storeu_si128(ptr, p0);
+    _mm_storeu_si128(ptr.add(1), p1);
+    _mm_storeu_si128(ptr.add(2), p2);
+    _mm_storeu_si128(ptr.add(3), p3);
+    result
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon", enable = "aes")]
+unsafe fn kernel_aarch64_neon(a: &[u64; LANES], b: &[u64; LANES]) -> [u128; LANES] {
+    use std::arch::aarch64::vmull_p64;
+
+    // Safety: target_features "neon" and "aes" are available in this function.
+    [
+        vmull_p64(a[0], b[0]),
+        vmull_p64(a[1], b[1]),
+        vmull_p64(a[2], b[2]),
+        vmull_p64(a[3], b[3]),
+    ]
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2", enable = "vpclmulqdq")]
+unsafe fn kernel_x86_vpclmul_avx2(
+    a: &[u64; WIDE_LANES],
+    b: &[u64; WIDE_LANES],
+) -> [u128; WIDE_LANES] {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{
+        __m256i, _mm256_clmulepi64_epi128, _mm256_loadu_si256, _mm256_permute2x128_si256,
+        _mm256_storeu_si256,
+    };
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{
+        __m256i, _mm256_clmulepi64_epi128, _mm256_loadu_si256, _mm256_permute2x128_si256,
+        _mm256_storeu_si256,
+    };
+
+    // Each register holds 4 lanes.
+    // Safety:
+    // - target_feature "avx2" is available in this function,
+    // - a and b point to 8 * 64 bits (no alignment required by this function).
+    let x0: __m256i = _mm256_loadu_si256(a.as_ptr() as *const __m256i);
+    let x1: __m256i = _mm256_loadu_si256(a.as_ptr().add(4) as *const __m256i);
+    let y0: __m256i = _mm256_loadu_si256(b.as_ptr() as *const __m256i);
+    let y1: __m256i = _mm256_loadu_si256(b.as_ptr().add(4) as *const __m256i);
+
+    // Each instruction multiplies the even or odd lanes within each 128-bit half.
+    // Safety: target_feature "vpclmulqdq" is available in this function.
+    let even0: __m256i = _mm256_clmulepi64_epi128(x0, y0, 0x00);
+    let odd0: __m256i = _mm256_clmulepi64_epi128(x0, y0, 0x11);
+    let even1: __m256i = _mm256_clmulepi64_epi128(x1, y1, 0x00);
+    let odd1: __m256i = _mm256_clmulepi64_epi128(x1, y1, 0x11);
+
+    // Interleave the even and odd products back into the order of the lanes.
+    // Safety: target_feature "avx2" is available in this function.
+    let p0: __m256i = _mm256_permute2x128_si256(even0, odd0, 0x20);
+    let p1: __m256i = _mm256_permute2x128_si256(even0, odd0, 0x31);
+    let p2: __m256i = _mm256_permute2x128_si256(even1, odd1, 0x20);
+    let p3: __m256i = _mm256_permute2x128_si256(even1, odd1, 0x31);
+
+    let mut result: [u128; WIDE_LANES] = [0; WIDE_LANES];
+    // Safety:
+    // - target_feature "avx2" is available in this function,
+    // - result points to 8 * 128 bits (no alignment required by this function).
+    let ptr = result.as_mut_ptr() as *mut __m256i;
+    _mm256_storeu_si256(ptr, p0);
+    _mm256_storeu_si256(ptr.add(1), p1);
+    _mm256_storeu_si256(ptr.add(2), p2);
+    _mm256_storeu_si256(ptr.add(3), p3);
+    result
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx512f", enable = "vpclmulqdq")]
+unsafe fn kernel_x86_vpclmul_avx512(
+    a: &[u64; WIDE_LANES],
+    b: &[u64; WIDE_LANES],
+) -> [u128; WIDE_LANES] {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{
+        __m512i, _mm512_clmulepi64_epi128, _mm512_loadu_epi64, _mm512_permutex2var_epi64,
+        _mm512_setr_epi64, _mm512_storeu_epi64,
+    };
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{
+        __m512i, _mm512_clmulepi64_epi128, _mm512_loadu_epi64, _mm512_permutex2var_epi64,
+        _mm512_setr_epi64, _mm512_storeu_epi64,
+    };
+
+    // A single register holds the 8 lanes.
+    // Safety:
+    // - target_feature "avx512f" is available in this function,
+    // - a and b point to 8 * 64 bits (no alignment required by this function).
+    let x: __m512i = _mm512_loadu_epi64(a.as_ptr() as *const i64);
+    let y: __m512i = _mm512_loadu_epi64(b.as_ptr() as *const i64);
+
+    // Each instruction multiplies the even or odd lanes within each 128-bit quarter.
+    // Safety: target_feature "vpclmulqdq" is available in this function.
+    let even: __m512i = _mm512_clmulepi64_epi128(x, y, 0x00);
+    let odd: __m512i = _mm512_clmulepi64_epi128(x, y, 0x11);
+
+    // Interleave the even and odd products back into the order of the lanes. Indices 0 to 7 select
+    // 64-bit words of the even products, and indices 8 to 15 select those of the odd products.
+    // Safety: target_feature "avx512f" is available in this function.
+    let p0: __m512i =
+        _mm512_permutex2var_epi64(even, _mm512_setr_epi64(0, 1, 8, 9, 2, 3, 10, 11), odd);
+    let p1: __m512i =
+        _mm512_permutex2var_epi64(even, _mm512_setr_epi64(4, 5, 12, 13, 6, 7, 14, 15), odd);
+
+    let mut result: [u128; WIDE_LANES] = [0; WIDE_LANES];
+    // Safety:
+    // - target_feature "avx512f" is available in this function,
+    // - result points to 8 * 128 bits (no alignment required by this function).
+    let ptr = result.as_mut_ptr() as *mut i64;
+    _mm512_storeu_epi64(ptr, p0);
+    _mm512_storeu_epi64(ptr.add(8), p1);
+    result
+}
+
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v7", enable = "neon")]
+unsafe fn kernel_arm_neon(a: &[u64; LANES], b: &[u64; LANES]) -> [u128; LANES] {
+    // Safety: target_features "v7" and "neon" are available in this function.
+    [
+        pmul_strategy_arm_neon(a[0], b[0]).0,
+        pmul_strategy_arm_neon(a[1], b[1]).0,
+        pmul_strategy_arm_neon(a[2], b[2]).0,
+        pmul_strategy_arm_neon(a[3], b[3]).0,
+    ]
+}
+
+#[cfg(target_arch = "arm")]
+#[target_feature(enable = "v8", enable = "neon", enable = "aes")]
+unsafe fn kernel_arm_pmull(a: &[u64; LANES], b: &[u64; LANES]) -> [u128; LANES] {
+    use std::arch::arm::vmull_p64;
+
+    // Safety: target_features "v8", "neon" and "aes" are available in this function.
+    [
+        vmull_p64(a[0], b[0]),
+        vmull_p64(a[1], b[1]),
+        vmull_p64(a[2], b[2]),
+        vmull_p64(a[3], b[3]),
+    ]
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::pmul::{pmul_strategy, pmul_strategy_nosimd_reference};
+    use crate::testutil::{bench_strategy, random_u64s, RANDOM_SEED};
+    use std::hint::black_box;
+    use test::Bencher;
+
+    #[test]
+    fn test_pmul_batch() {
+        let a = [
+            0x1234567890abcdef,
+            0,
+            1,
+            0xffffffffffffffff,
+            0x8000000000000000,
+        ];
+        let b = [
+            0xfedcba0987654321,
+            0xffffffffffffffff,
+            0xfedcba0987654321,
+            0xffffffffffffffff,
+            0x8000000000000000,
+        ];
+        let mut out = [0; 5];
+        let _strategy = pmul_batch(&a, &b, &mut out);
+        assert_eq!(
+            out,
+            [
+                0x0e038d8eab3af47a1f31f87ebb8c810f,
+                0,
+                0xfedcba0987654321,
+                0x55555555555555555555555555555555,
+                0x40000000000000000000000000000000,
+            ]
+        );
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(_strategy, "aarch64_neon");
+
+        pmul_batch_accumulate(&a, &b, &mut out);
+        assert_eq!(out, [0; 5]);
+    }
+
+    #[test]
+    fn test_preferred_batch_strategy() {
+        let strategy = preferred_batch_strategy();
+        assert!(strategy.is_available());
+        assert_eq!(pmul_batch(&[1], &[1], &mut [0]), strategy.name());
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        assert_eq!(strategy, preferred_strategy());
+    }
+
+    #[test]
+    fn test_pmul_batch_cross_check() {
+        // All the lengths of the last group of lanes.
+        for len in 0..=2 * WIDE_LANES + 1 {
+            let a = random_u64s(len, RANDOM_SEED);
+            let b = random_u64s(len, 0x13198a2e03707344);
+            let expected = a
+                .iter()
+                .zip(&b)
+                .map(|(&a, &b)| pmul_strategy_nosimd_reference(a, b).0)
+                .collect::<Vec<_>>();
+
+            for strategy in PmulStrategy::ALL {
+                let mut out = vec![0; len];
+                match pmul_batch_with(strategy, &a, &b, &mut out) {
+                    Ok(()) => assert_eq!(out, expected, "{strategy:?}"),
+                    Err(e) => {
+                        assert_eq!(e, Unsupported(strategy));
+                        continue;
+                    }
+                }
+
+                // Accumulating the same products again cancels them.
+                let mut acc = vec![0x0123456789abcdeffedcba9876543210; len];
+                pmul_batch_accumulate_with(strategy, &a, &b, &mut acc).unwrap();
+                for (acc, expected) in acc.iter().zip(&expected) {
+                    assert_eq!(acc ^ expected, 0x0123456789abcdeffedcba9876543210);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Output slice has a different length")]
+    fn test_pmul_batch_wrong_length() {
+        pmul_batch(&[1, 2], &[3, 4], &mut [0; 3]);
+    }
+
+    fn bench_inputs() -> (Vec<u64>, Vec<u64>, Vec<u128>) {
+        (
+            random_u64s(1024, RANDOM_SEED),
+            random_u64s(1024, 0x13198a2e03707344),
+            vec![0; 1024],
+        )
+    }
+
+    #[bench]
+    fn bench_pmul_scalar_loop(bencher: &mut Bencher) {
+        let (a, b, mut out) = bench_inputs();
+        bencher.iter(|| {
+            for ((a, b), out) in a.iter().zip(&b).zip(&mut out) {
+                *out = pmul_strategy(black_box(*a), black_box(*b)).0;
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_pmul_batch(bencher: &mut Bencher) {
+        let (a, b, mut out) = bench_inputs();
+        bencher.iter(|| pmul_batch(black_box(&a), black_box(&b), &mut out));
+    }
+
+    #[bench]
+    fn bench_pmul_batch_accumulate(bencher: &mut Bencher) {
+        let (a, b, mut out) = bench_inputs();
+        bencher.iter(|| pmul_batch_accumulate(black_box(&a), black_box(&b), &mut out));
+    }
+
+    fn bench_pmul_batch_with(bencher: &mut Bencher, strategy: PmulStrategy) {
+        bench_strategy(bencher, strategy.is_available(), |bencher| {
+            let (a, b, mut out) = bench_inputs();
+            bencher.iter(|| pmul_batch_with(strategy, black_box(&a), black_box(&b), &mut out));
+        });
+    }
+
+    #[bench]
+    fn bench_pmul_batch_with_nosimd(bencher: &mut Bencher) {
+        bench_pmul_batch_with(bencher, PmulStrategy::Nosimd);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_pmul_batch_with_x86_clmul(bencher: &mut Bencher) {
+        bench_pmul_batch_with(bencher, PmulStrategy::X86Clmul);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[bench]
+    fn bench_pmul_batch_with_aarch64_neon(bencher: &mut Bencher) {
+        bench_pmul_batch_with(bencher, PmulStrategy::Aarch64Neon);
+    }
+
+    #[cfg(target_arch = "arm")]
+    #[bench]
+    fn bench_pmul_batch_with_arm_neon(bencher: &mut Bencher) {
+        bench_pmul_batch_with(bencher, PmulStrategy::ArmNeon);
+    }
+
+    #[cfg(target_arch = "arm")]
+    #[bench]
+    fn bench_pmul_batch_with_arm_pmull(bencher: &mut Bencher) {
+        bench_pmul_batch_with(bencher, PmulStrategy::ArmPmull);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_pmul_batch_with_x86_vpclmul_avx2(bencher: &mut Bencher) {
+        bench_pmul_batch_with(bencher, PmulStrategy::X86VpclmulAvx2);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[bench]
+    fn bench_pmul_batch_with_x86_vpclmul_avx512(bencher: &mut Bencher) {
+        bench_pmul_batch_with(bencher, PmulStrategy::X86VpclmulAvx512);
+    }
+}
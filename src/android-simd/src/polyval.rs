@@ -0,0 +1,373 @@
+//! GHASH (GCM) and POLYVAL (GCM-SIV) universal hashes, built on the carry-less multiplication.
+//!
+//! Both evaluate a polynomial in GF(2^128) whose coefficients are the 16-byte blocks of the
+//! message, at the point given by the key. POLYVAL reads the blocks as little-endian elements of
+//! GF(2)[x] / (x^128 + x^127 + x^126 + x^121 + 1), and GHASH is computed as a POLYVAL of the
+//! byte-reversed blocks, with the key multiplied by x (RFC 8452, Appendix A).
+//!
+//! Four blocks are processed per iteration with aggregated reduction: their 256-bit products by
+//! the precomputed powers H^4, H^3, H^2 and H are summed, and only the sum is reduced.
+
+use crate::pmul::{preferred_strategy, Pmul128, PmulStrategy, Unsupported};
+use std::fmt;
+
+/// Size of a block, in bytes.
+pub const BLOCK_LEN: usize = 16;
+
+/// Number of blocks processed per iteration.
+const AGGREGATED_BLOCKS: usize = 4;
+
+/// The modulus x^128 + x^127 + x^126 + x^121 + 1, without its x^128 term.
+const MODULUS: u128 = 0xc2000000000000000000000000000001;
+
+/// Multiplies a 256-bit product, given as `[low, high]` halves, by x^-128 and reduces it modulo
+/// the POLYVAL polynomial. This is the Montgomery reduction of RFC 8452, folding each 64-bit word
+/// of the low half with shifts, which runs in constant time.
+fn mont_reduce(product: [u128; 2]) -> u128 {
+    let [v0, v1] = [product[0] as u64, (product[0] >> 64) as u64];
+    let [mut v2, mut v3] = [product[1] as u64, (product[1] >> 64) as u64];
+    let v1 = v1 ^ (v0 << 63) ^ (v0 << 62) ^ (v0 << 57);
+    v2 ^= v0 ^ (v0 >> 1) ^ (v0 >> 2) ^ (v0 >> 7);
+    v2 ^= (v1 << 63) ^ (v1 << 62) ^ (v1 << 57);
+    v3 ^= v1 ^ (v1 >> 1) ^ (v1 >> 2) ^ (v1 >> 7);
+    (v2 as u128) | ((v3 as u128) << 64)
+}
+
+/// Multiplies a field element by x, i.e. mulX_POLYVAL() of RFC 8452.
+fn mul_x(h: u128) -> u128 {
+    // The mask is all ones if the x^127 coefficient is set, without branching on it.
+    let carry = ((h as i128) >> 127) as u128;
+    (h << 1) ^ (carry & MODULUS)
+}
+
+/// POLYVAL universal hash, as specified by RFC 8452.
+#[derive(Clone)]
+pub struct Polyval {
+    /// Multiplication resolved once, as the aggregated loop computes 4 products per iteration.
+    pmul: Pmul128,
+    /// The key powers H, H^2, H^3 and H^4, each including the factor x^-128 of the reduction.
+    powers: [u128; AGGREGATED_BLOCKS],
+    accumulator: u128,
+}
+
+impl Polyval {
+    /// Creates a hash with the given key, and the strategy selected by `preferred_strategy()`.
+    pub fn new(key: &[u8; BLOCK_LEN]) -> Self {
+        Self::new_with(preferred_strategy(), key).expect("Preferred strategy is unsupported")
+    }
+
+    /// Creates a hash with the given key and strategy, if the current CPU supports it.
+    pub fn new_with(strategy: PmulStrategy, key: &[u8; BLOCK_LEN]) -> Result<Self, Unsupported> {
+        Ok(Self::from_element(
+            Pmul128::new(strategy)?,
+            u128::from_le_bytes(*key),
+        ))
+    }
+
+    fn from_element(pmul: Pmul128, h: u128) -> Self {
+        let mut result = Polyval {
+            pmul,
+            powers: [h; AGGREGATED_BLOCKS],
+            accumulator: 0,
+        };
+        for i in 1..AGGREGATED_BLOCKS {
+            result.powers[i] = mont_reduce(result.mul(result.powers[i - 1], h));
+        }
+        result
+    }
+
+    /// Returns the strategy of the carry-less multiplication.
+    pub fn strategy(&self) -> PmulStrategy {
+        self.pmul.strategy()
+    }
+
+    /// Absorbs the data, padding its last block with zeros if it's partial. Hashing a message in
+    /// several calls is therefore only equivalent to hashing it at once if all but the last calls
+    /// are given a multiple of `BLOCK_LEN` bytes.
+    pub fn update_padded(&mut self, data: &[u8]) {
+        self.update::<false>(data);
+    }
+
+    /// Returns the hash of the data absorbed so far.
+    pub fn finalize(self) -> [u8; BLOCK_LEN] {
+        self.accumulator.to_le_bytes()
+    }
+
+    /// Absorbs the data as blocks in little-endian or big-endian order.
+    fn update<const BIG_ENDIAN: bool>(&mut self, data: &[u8]) {
+        let read = |block: &[u8; BLOCK_LEN]| {
+            if BIG_ENDIAN {
+                u128::from_be_bytes(*block)
+            } else {
+                u128::from_le_bytes(*block)
+            }
+        };
+
+        let mut chunks = data.chunks_exact(AGGREGATED_BLOCKS * BLOCK_LEN);
+        for chunk in &mut chunks {
+            let (blocks, _) = chunk.as_chunks::<BLOCK_LEN>();
+            // The first block is multiplied by the highest power, together with the accumulator.
+            let mut sum = [0; 2];
+            for (i, block) in blocks.iter().enumerate() {
+                let mut x = read(block);
+                if i == 0 {
+                    x ^= self.accumulator;
+                }
+                let product = self.mul(x, self.powers[AGGREGATED_BLOCKS - 1 - i]);
+                sum = [sum[0] ^ product[0], sum[1] ^ product[1]];
+            }
+            self.accumulator = mont_reduce(sum);
+        }
+
+        let (blocks, partial) = chunks.remainder().as_chunks::<BLOCK_LEN>();
+        let mut padded = [0; BLOCK_LEN];
+        padded[..partial.len()].copy_from_slice(partial);
+        let last = if partial.is_empty() {
+            None
+        } else {
+            Some(&padded)
+        };
+        for block in blocks.iter().chain(last) {
+            let x = read(block) ^ self.accumulator;
+            self.accumulator = mont_reduce(self.mul(x, self.powers[0]));
+        }
+    }
+
+    fn mul(&self, a: u128, b: u128) -> [u128; 2] {
+        self.pmul.mul(a, b)
+    }
+}
+
+// The key powers are secret, so they must not end up in the logs.
+impl fmt::Debug for Polyval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Polyval")
+            .field("strategy", &self.strategy())
+            .field("key_len", &BLOCK_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+/// GHASH universal hash, as specified by NIST SP 800-38D for GCM.
+#[derive(Clone)]
+pub struct Ghash(Polyval);
+
+impl Ghash {
+    /// Creates a hash with the given key, and the strategy selected by `preferred_strategy()`.
+    pub fn new(key: &[u8; BLOCK_LEN]) -> Self {
+        Self::new_with(preferred_strategy(), key).expect("Preferred strategy is unsupported")
+    }
+
+    /// Creates a hash with the given key and strategy, if the current CPU supports it.
+    pub fn new_with(strategy: PmulStrategy, key: &[u8; BLOCK_LEN]) -> Result<Self, Unsupported> {
+        let h = mul_x(u128::from_be_bytes(*key));
+        Ok(Ghash(Polyval::from_element(Pmul128::new(strategy)?, h)))
+    }
+
+    /// Returns the strategy of the carry-less multiplication.
+    pub fn strategy(&self) -> PmulStrategy {
+        self.0.strategy()
+    }
+
+    /// Absorbs the data, padding its last block with zeros if it's partial, as GCM does for the
+    /// additional data and the ciphertext.
+    pub fn update_padded(&mut self, data: &[u8]) {
+        self.0.update::<true>(data);
+    }
+
+    /// Returns the hash of the data absorbed so far.
+    pub fn finalize(self) -> [u8; BLOCK_LEN] {
+        self.0.accumulator.to_be_bytes()
+    }
+}
+
+impl fmt::Debug for Ghash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ghash")
+            .field("strategy", &self.strategy())
+            .field("key_len", &BLOCK_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::pmul::available_strategies;
+    use crate::testutil::{from_hex, hex_bytes};
+    use std::hint::black_box;
+    use test::Bencher;
+
+    /// Input of GHASH in GCM, with the padded additional data and ciphertext, and their lengths in
+    /// bits.
+    fn ghash_gcm(strategy: PmulStrategy, h: &str, aad: &str, ciphertext: &str) -> [u8; BLOCK_LEN] {
+        let (aad, ciphertext) = (hex_bytes(aad), hex_bytes(ciphertext));
+        let mut lengths = [0; BLOCK_LEN];
+        lengths[..8].copy_from_slice(&(aad.len() as u64 * 8).to_be_bytes());
+        lengths[8..].copy_from_slice(&(ciphertext.len() as u64 * 8).to_be_bytes());
+
+        let mut ghash = Ghash::new_with(strategy, &from_hex(h)).unwrap();
+        ghash.update_padded(&aad);
+        ghash.update_padded(&ciphertext);
+        ghash.update_padded(&lengths);
+        ghash.finalize()
+    }
+
+    /// Example of RFC 8452, Appendix A.
+    #[test]
+    fn test_polyval_rfc8452() {
+        for strategy in available_strategies() {
+            let mut polyval =
+                Polyval::new_with(strategy, &from_hex("25629347589242761d31f826ba4b757b")).unwrap();
+            polyval.update_padded(&hex_bytes(
+                "4f4f95668c83dfb6401762bb2d01a262d1a24ddd2721d006bbe45f20d3c9f362",
+            ));
+            assert_eq!(
+                polyval.finalize(),
+                from_hex("f7a3b47b846119fae5b7866cf5e5b77e"),
+                "{strategy:?}"
+            );
+        }
+    }
+
+    /// POLYVAL of the second AES-128-GCM-SIV example of RFC 8452, Appendix C.1.
+    #[test]
+    fn test_polyval_gcm_siv() {
+        for strategy in available_strategies() {
+            let mut polyval =
+                Polyval::new_with(strategy, &from_hex("d9b360279694941ac5dbc6987ada7377")).unwrap();
+            polyval.update_padded(&from_hex::<8>("0100000000000000"));
+            polyval.update_padded(&from_hex::<16>("00000000000000004000000000000000"));
+            assert_eq!(
+                polyval.finalize(),
+                from_hex("eb93b7740962c5e49d2a90a7dc5cec74"),
+                "{strategy:?}"
+            );
+        }
+    }
+
+    /// Example of RFC 8452, Appendix A.
+    #[test]
+    fn test_ghash_rfc8452() {
+        for strategy in available_strategies() {
+            let mut ghash =
+                Ghash::new_with(strategy, &from_hex("dcbaa5dd137c188ebb21492c23c9b112")).unwrap();
+            ghash.update_padded(&hex_bytes(
+                "62a2012dbb621740b6df838c66954f4f62f3c9d3205fe4bb06d02127dd4da2d1",
+            ));
+            assert_eq!(
+                ghash.finalize(),
+                from_hex("7eb7e5f56c86b7e5fa1961847bb4a3f7"),
+                "{strategy:?}"
+            );
+        }
+    }
+
+    /// Test cases 2 to 4 of "The Galois/Counter Mode of Operation (GCM)", McGrew and Viega.
+    #[test]
+    fn test_ghash_gcm() {
+        let ciphertext = "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+                          21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985";
+        for strategy in available_strategies() {
+            assert_eq!(
+                ghash_gcm(
+                    strategy,
+                    "66e94bd4ef8a2c3b884cfa59ca342b2e",
+                    "",
+                    "0388dace60b6a392f328c2b971b2fe78"
+                ),
+                from_hex("f38cbb1ad69223dcc3457ae5b6b0f885"),
+                "{strategy:?}"
+            );
+            assert_eq!(
+                ghash_gcm(strategy, "b83b533708bf535d0aa6e52980d53b78", "", ciphertext),
+                from_hex("7f1b32b81b820d02614f8895ac1d4eac"),
+                "{strategy:?}"
+            );
+            assert_eq!(
+                ghash_gcm(
+                    strategy,
+                    "b83b533708bf535d0aa6e52980d53b78",
+                    "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+                    &ciphertext[..120]
+                ),
+                from_hex("698e57f70e6ecc7fd9463b7260a9ae5f"),
+                "{strategy:?}"
+            );
+        }
+    }
+
+    /// The aggregated reduction matches the reduction of each block, for all the lengths of the
+    /// last group of blocks.
+    #[test]
+    fn test_polyval_aggregated() {
+        let key = from_hex("25629347589242761d31f826ba4b757b");
+        let data = (0..=255).collect::<Vec<u8>>();
+        for len in 0..=2 * AGGREGATED_BLOCKS * BLOCK_LEN + 1 {
+            let mut expected = Polyval::new_with(PmulStrategy::Nosimd, &key).unwrap();
+            for block in data[..len].chunks(BLOCK_LEN) {
+                expected.update_padded(block);
+            }
+            let mut polyval = Polyval::new(&key);
+            polyval.update_padded(&data[..len]);
+            assert_eq!(polyval.finalize(), expected.finalize(), "{len}");
+        }
+    }
+
+    #[test]
+    fn test_mul_x() {
+        assert_eq!(mul_x(1), 2);
+        assert_eq!(mul_x(1 << 127), MODULUS);
+        assert_eq!(mul_x(3 << 126), MODULUS ^ (1 << 127));
+    }
+
+    #[test]
+    fn test_new_with_unsupported() {
+        for strategy in PmulStrategy::ALL {
+            if !strategy.is_available() {
+                assert_eq!(
+                    Polyval::new_with(strategy, &[0; BLOCK_LEN]).unwrap_err(),
+                    Unsupported(strategy)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_debug_hides_key() {
+        let key = [0xab; BLOCK_LEN];
+        let strategy = PmulStrategy::Nosimd;
+        assert_eq!(
+            format!("{:?}", Polyval::new_with(strategy, &key).unwrap()),
+            "Polyval { strategy: Nosimd, key_len: 16, .. }"
+        );
+        assert_eq!(
+            format!("{:?}", Ghash::new_with(strategy, &key).unwrap()),
+            "Ghash { strategy: Nosimd, key_len: 16, .. }"
+        );
+    }
+
+    fn bench_update(b: &mut Bencher, mut update: impl FnMut(&[u8])) {
+        let data = vec![0x5a; 4096];
+        b.bytes = data.len() as u64;
+        b.iter(|| update(black_box(&data)));
+    }
+
+    #[bench]
+    fn bench_polyval(b: &mut Bencher) {
+        let mut polyval = Polyval::new(&[0x42; BLOCK_LEN]);
+        bench_update(b, |data| polyval.update_padded(data));
+    }
+
+    #[bench]
+    fn bench_polyval_nosimd(b: &mut Bencher) {
+        let mut polyval = Polyval::new_with(PmulStrategy::Nosimd, &[0x42; BLOCK_LEN]).unwrap();
+        bench_update(b, |data| polyval.update_padded(data));
+    }
+
+    #[bench]
+    fn bench_ghash(b: &mut Bencher) {
+        let mut ghash = Ghash::new(&[0x42; BLOCK_LEN]);
+        bench_update(b, |data| ghash.update_padded(data));
+    }
+}
@@ -0,0 +1,539 @@
+//! Known-answer self-tests of the implementations, run at initialization. Strategies that produce a
+//! wrong output are disabled, so that the dispatchers fall back to another strategy.
+
+#[cfg(all(
+    feature = "relink",
+    any(target_arch = "aarch64", target_arch = "x86_64")
+))]
+use crate::aes::{aesenc_with, Aes, AesStrategy, BLOCK_LEN};
+use crate::crc::{crc_with, CrcAlgorithm, CrcStrategy};
+use crate::dispatch;
+#[cfg(all(feature = "relink", target_arch = "aarch64"))]
+use crate::gf2n::{gf128_invert_with, gf256_invert_with, gf64_invert_with, Gf2nStrategy};
+use crate::logger::{Level, Logger};
+use crate::pmul::{available_strategies, pmul128_with, pmul256_with, pmul_with};
+use crate::pmul_batch::{pmul_batch_accumulate_with, pmul_batch_with};
+use crate::polyval::{Ghash, Polyval};
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Set of strategies disabled because they failed a self-test, indexed by their position in their
+/// enum.
+pub struct DisabledStrategies(AtomicU32);
+
+impl DisabledStrategies {
+    pub const fn new() -> Self {
+        Self(AtomicU32::new(0))
+    }
+
+    /// Disables the strategy at the given index, and makes the dispatchers select their strategy
+    /// again.
+    pub fn disable(&self, index: usize) {
+        self.0.fetch_or(1 << index, Ordering::Relaxed);
+        dispatch::invalidate();
+    }
+
+    /// Returns whether the strategy at the given index is disabled.
+    pub fn is_disabled(&self, index: usize) -> bool {
+        self.0.load(Ordering::Relaxed) & (1 << index) != 0
+    }
+}
+
+/// Wrong output of a strategy on a known-answer test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfTestFailure {
+    pub operation: &'static str,
+    pub strategy: &'static str,
+    pub input: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Display for SelfTestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [strategy = {}]: input {}, expected {}, got {}",
+            self.operation, self.strategy, self.input, self.expected, self.actual
+        )
+    }
+}
+
+/// Known answers of the polynomial multiplication, as (a, b, product).
+const PMUL_VECTORS: [(u64, u64, u128); 5] = [
+    (
+        0x1234567890abcdef,
+        0xfedcba0987654321,
+        0x0e038d8eab3af47a1f31f87ebb8c810f,
+    ),
+    (0, 0xffffffffffffffff, 0),
+    (1, 0xfedcba0987654321, 0xfedcba0987654321),
+    (
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x55555555555555555555555555555555,
+    ),
+    (
+        0x8000000000000000,
+        0x8000000000000000,
+        0x40000000000000000000000000000000,
+    ),
+];
+
+/// Known answers of the 128-bit polynomial multiplication, as (a, b, product).
+const PMUL128_VECTORS: [(u128, u128, [u128; 2]); 2] = [
+    (
+        0x0123456789abcdeffedcba9876543210,
+        0xfedcba98765432100123456789abcdef,
+        [
+            0x55b56d8d3ddd05e540a0789828c810f0,
+            0x00e038d8688850b015f52dcd7d9d45a5,
+        ],
+    ),
+    (
+        0xffffffffffffffffffffffffffffffff,
+        0xffffffffffffffffffffffffffffffff,
+        [
+            0x55555555555555555555555555555555,
+            0x55555555555555555555555555555555,
+        ],
+    ),
+];
+
+/// Known answer of the 256-bit polynomial multiplication, as (a, b, product).
+const PMUL256_VECTOR: ([u128; 2], [u128; 2], [u128; 4]) = (
+    [
+        0x1234567890abcdeffedcba0987654321,
+        0x0123456789abcdeffedcba9876543210,
+    ],
+    [
+        0x0f1e2d3c4b5a69788796a5b4c3d2e1f0,
+        0xfedcba98765432100123456789abcdef,
+    ],
+    [
+        0x5e86abe6df9073b7bebc7ecc82e0cff0,
+        0x73285b7151d85e62d5afd8cb0a38dba1,
+        0x5bb80f37bce4a9f008104cb2af618561,
+        0x00e038d8688850b015f52dcd7d9d45a5,
+    ],
+);
+
+/// Known answers of the universal hashes, as (key, message blocks, hash), from RFC 8452,
+/// Appendix A.
+const POLYVAL_VECTOR: ([u8; 16], [[u8; 16]; 2], [u8; 16]) = (
+    0x25629347589242761d31f826ba4b757b_u128.to_be_bytes(),
+    [
+        0x4f4f95668c83dfb6401762bb2d01a262_u128.to_be_bytes(),
+        0xd1a24ddd2721d006bbe45f20d3c9f362_u128.to_be_bytes(),
+    ],
+    0xf7a3b47b846119fae5b7866cf5e5b77e_u128.to_be_bytes(),
+);
+const GHASH_VECTOR: ([u8; 16], [[u8; 16]; 2], [u8; 16]) = (
+    0xdcbaa5dd137c188ebb21492c23c9b112_u128.to_be_bytes(),
+    [
+        0x62a2012dbb621740b6df838c66954f4f_u128.to_be_bytes(),
+        0x62f3c9d3205fe4bb06d02127dd4da2d1_u128.to_be_bytes(),
+    ],
+    0x7eb7e5f56c86b7e5fa1961847bb4a3f7_u128.to_be_bytes(),
+);
+
+/// Known answers of the CRCs, as (algorithm, CRC of "123456789", CRC of the bytes 0 to 255). The
+/// former is the check value of the CRC catalogue, and the latter covers the folded blocks.
+const CRC_VECTORS: [(CrcAlgorithm, u64, u64); 4] = [
+    (CrcAlgorithm::Crc32, 0xcbf43926, 0x29058c73),
+    (CrcAlgorithm::Crc32c, 0xe3069283, 0x9c44184b),
+    (
+        CrcAlgorithm::Crc64Xz,
+        0x995dc9bbdf1939fa,
+        0x72414b2f65db3ab0,
+    ),
+    (
+        CrcAlgorithm::Crc64Nvme,
+        0xae8b14860a799888,
+        0xff71e21279d9966e,
+    ),
+];
+
+/// Example vectors of FIPS-197, Appendix C: the key length, and the ciphertext of the plaintext
+/// 00112233445566778899aabbccddeeff with the key made of the bytes 0, 1, 2, etc.
+#[cfg(all(
+    feature = "relink",
+    any(target_arch = "aarch64", target_arch = "x86_64")
+))]
+const AES_VECTORS: [(usize, u128); 3] = [
+    (16, 0x69c4e0d86a7b0430d8cdb78070b4c55a),
+    (24, 0xdda97ca4864cdfe06eaf70a0ec0d7191),
+    (32, 0x8ea2b7ca516745bfeafc49904b496089),
+];
+
+/// Runs the known-answer tests against all the strategies available on this CPU, disables the
+/// strategies that fail, and returns the failures. Each result is reported to the logger.
+pub fn run_self_tests(logger: &dyn Logger) -> Vec<SelfTestFailure> {
+    let mut tests = SelfTests {
+        logger,
+        failures: Vec::new(),
+    };
+
+    for strategy in available_strategies() {
+        let mut passed = true;
+        for (a, b, expected) in PMUL_VECTORS {
+            let actual = pmul_with(strategy, a, b).expect("Available strategy is unsupported");
+            passed &= tests.check(
+                "pmul",
+                strategy.name(),
+                format!("{:016x?}", [a, b]),
+                format!("{expected:032x}"),
+                format!("{actual:032x}"),
+            );
+        }
+        for (a, b, expected) in PMUL128_VECTORS {
+            let actual = pmul128_with(strategy, a, b).expect("Available strategy is unsupported");
+            passed &= tests.check(
+                "pmul128",
+                strategy.name(),
+                format!("{:032x?}", [a, b]),
+                format!("{expected:032x?}"),
+                format!("{actual:032x?}"),
+            );
+        }
+        let (a, b, expected) = PMUL256_VECTOR;
+        let actual = pmul256_with(strategy, a, b).expect("Available strategy is unsupported");
+        passed &= tests.check(
+            "pmul256",
+            strategy.name(),
+            format!("{:032x?}", [a, b]),
+            format!("{expected:032x?}"),
+            format!("{actual:032x?}"),
+        );
+
+        // The batched implementations process the vectors as lanes, including a partial group.
+        let a = PMUL_VECTORS.map(|(a, _, _)| a);
+        let b = PMUL_VECTORS.map(|(_, b, _)| b);
+        let expected = PMUL_VECTORS.map(|(_, _, product)| product);
+        let mut actual = [0; PMUL_VECTORS.len()];
+        pmul_batch_with(strategy, &a, &b, &mut actual).expect("Available strategy is unsupported");
+        passed &= tests.check(
+            "pmul_batch",
+            strategy.name(),
+            format!("{:016x?}", [a, b]),
+            format!("{expected:032x?}"),
+            format!("{actual:032x?}"),
+        );
+        pmul_batch_accumulate_with(strategy, &a, &b, &mut actual)
+            .expect("Available strategy is unsupported");
+        passed &= tests.check(
+            "pmul_batch_accumulate",
+            strategy.name(),
+            format!("{:016x?}", [a, b]),
+            format!("{:032x?}", [0u128; PMUL_VECTORS.len()]),
+            format!("{actual:032x?}"),
+        );
+
+        let (key, message, expected) = POLYVAL_VECTOR;
+        let mut polyval =
+            Polyval::new_with(strategy, &key).expect("Available strategy is unsupported");
+        polyval.update_padded(&message.concat());
+        passed &= tests.check(
+            "polyval",
+            strategy.name(),
+            format!("{:02x?}", [key, message[0], message[1]]),
+            format!("{expected:02x?}"),
+            format!("{:02x?}", polyval.finalize()),
+        );
+        let (key, message, expected) = GHASH_VECTOR;
+        let mut ghash = Ghash::new_with(strategy, &key).expect("Available strategy is unsupported");
+        ghash.update_padded(&message.concat());
+        passed &= tests.check(
+            "ghash",
+            strategy.name(),
+            format!("{:02x?}", [key, message[0], message[1]]),
+            format!("{expected:02x?}"),
+            format!("{:02x?}", ghash.finalize()),
+        );
+        tests.conclude("pmul", strategy.name(), passed);
+        if !passed {
+            strategy.disable();
+        }
+    }
+
+    let bytes = (0..=255).collect::<Vec<u8>>();
+    for strategy in CrcStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+        let mut passed = true;
+        for (algorithm, check, expected) in CRC_VECTORS {
+            if !strategy.supports(algorithm) {
+                continue;
+            }
+            for (data, expected) in [(&b"123456789"[..], check), (&bytes, expected)] {
+                let actual =
+                    crc_with(strategy, algorithm, data).expect("Available strategy is unsupported");
+                passed &= tests.check(
+                    algorithm.name(),
+                    strategy.name(),
+                    format!("{data:02x?}"),
+                    format!("{expected:016x}"),
+                    format!("{actual:016x}"),
+                );
+            }
+        }
+        tests.conclude("crc", strategy.name(), passed);
+        if !passed {
+            strategy.disable();
+        }
+    }
+
+    #[cfg(all(
+        feature = "relink",
+        any(target_arch = "aarch64", target_arch = "x86_64")
+    ))]
+    {
+        for strategy in AesStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+            let (src, key) = ([1; 16], [2; 16]);
+            let mut dst = src;
+            aesenc_with(strategy, &mut dst, &key).expect("Available strategy is unsupported");
+            let passed = tests.check(
+                "aesenc",
+                strategy.name(),
+                format!("{:02x?}", [src, key]),
+                format!("{:02x?}", [0x7e; 16]),
+                format!("{dst:02x?}"),
+            );
+            tests.conclude("aesenc", strategy.name(), passed);
+            if !passed {
+                strategy.disable();
+            }
+        }
+
+        for strategy in AesStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+            let mut passed = true;
+            let plaintext = 0x00112233445566778899aabbccddeeff_u128.to_be_bytes();
+            for (key_len, ciphertext) in AES_VECTORS {
+                let key = (0..key_len as u8).collect::<Vec<_>>();
+                let aes = Aes::new_with(strategy, &key).expect("Available strategy is unsupported");
+                let mut block = plaintext;
+                aes.encrypt_block(&mut block);
+                passed &= tests.check(
+                    "aes_encrypt",
+                    strategy.name(),
+                    format!("{:02x?}", [&key[..], &plaintext]),
+                    format!("{:02x?}", ciphertext.to_be_bytes()),
+                    format!("{block:02x?}"),
+                );
+                aes.decrypt_block(&mut block);
+                passed &= tests.check(
+                    "aes_decrypt",
+                    strategy.name(),
+                    format!("{:02x?}", [&key[..], &ciphertext.to_be_bytes()]),
+                    format!("{plaintext:02x?}"),
+                    format!("{block:02x?}"),
+                );
+
+                // Counter mode processes several blocks at once, which the block cipher checks
+                // above don't cover: its keystream must match the encryption of each counter.
+                let start = u128::MAX - 1;
+                let mut expected = [0; 9 * BLOCK_LEN + 5];
+                for (i, chunk) in expected.chunks_mut(BLOCK_LEN).enumerate() {
+                    let mut block = start.wrapping_add(i as u128).to_be_bytes();
+                    aes.encrypt_block(&mut block);
+                    chunk.copy_from_slice(&block[..chunk.len()]);
+                }
+                let mut keystream = [0; 9 * BLOCK_LEN + 5];
+                aes.apply_ctr(&mut start.to_be_bytes(), &mut keystream);
+                passed &= tests.check(
+                    "aes_ctr",
+                    strategy.name(),
+                    format!("{:02x?}", [&key[..], &start.to_be_bytes()]),
+                    format!("{expected:02x?}"),
+                    format!("{keystream:02x?}"),
+                );
+            }
+            tests.conclude("aes", strategy.name(), passed);
+            if !passed {
+                strategy.disable();
+            }
+        }
+    }
+
+    #[cfg(all(feature = "relink", target_arch = "aarch64"))]
+    {
+        // The inverse of X is derived from the modulus X^n + X^a + X^b + X^c + 1 of each field.
+        for strategy in Gf2nStrategy::ALL.into_iter().filter(|s| s.is_available()) {
+            let mut passed = true;
+            for (src, expected) in [([1], [1_u64]), ([2], [0x800000000000000d])] {
+                let mut dst = src;
+                gf64_invert_with(strategy, &mut dst).expect("Available strategy is unsupported");
+                passed &= tests.check(
+                    "gf64_invert",
+                    strategy.name(),
+                    format!("{src:016x?}"),
+                    format!("{expected:016x?}"),
+                    format!("{dst:016x?}"),
+                );
+            }
+            for (src, expected) in [([1, 0], [1_u64, 0]), ([2, 0], [0x43, 1 << 63])] {
+                let mut dst = src;
+                gf128_invert_with(strategy, &mut dst).expect("Available strategy is unsupported");
+                passed &= tests.check(
+                    "gf128_invert",
+                    strategy.name(),
+                    format!("{src:016x?}"),
+                    format!("{expected:016x?}"),
+                    format!("{dst:016x?}"),
+                );
+            }
+            for (src, expected) in [
+                ([1, 0, 0, 0], [1_u64, 0, 0, 0]),
+                ([2, 0, 0, 0], [0x212, 0, 0, 1 << 63]),
+            ] {
+                let mut dst = src;
+                gf256_invert_with(strategy, &mut dst).expect("Available strategy is unsupported");
+                passed &= tests.check(
+                    "gf256_invert",
+                    strategy.name(),
+                    format!("{src:016x?}"),
+                    format!("{expected:016x?}"),
+                    format!("{dst:016x?}"),
+                );
+            }
+            tests.conclude("gf2n", strategy.name(), passed);
+            if !passed {
+                strategy.disable();
+            }
+        }
+    }
+
+    tests.failures
+}
+
+/// State of a run of the self-tests.
+struct SelfTests<'a> {
+    logger: &'a dyn Logger,
+    failures: Vec<SelfTestFailure>,
+}
+
+impl SelfTests<'_> {
+    /// Compares the actual output of a strategy with the expected one, and records a failure if
+    /// they differ. Returns whether the test passed.
+    fn check(
+        &mut self,
+        operation: &'static str,
+        strategy: &'static str,
+        input: String,
+        expected: String,
+        actual: String,
+    ) -> bool {
+        if actual == expected {
+            return true;
+        }
+        // The failure is returned to the caller, so a failure to log isn't fatal.
+        let _ = self.logger.log_fields(
+            Level::Error,
+            "Self-test failed",
+            &[
+                ("operation", operation.into()),
+                ("strategy", strategy.into()),
+                ("input", input.as_str().into()),
+                ("expected", expected.as_str().into()),
+                ("output", actual.as_str().into()),
+            ],
+        );
+        self.failures.push(SelfTestFailure {
+            operation,
+            strategy,
+            input,
+            expected,
+            actual,
+        });
+        false
+    }
+
+    /// Reports whether all the tests of a strategy passed.
+    fn conclude(&self, operation: &str, strategy: &str, passed: bool) {
+        let (level, message) = if passed {
+            (Level::Debug, "Self-tests passed")
+        } else {
+            (Level::Warn, "Self-tests failed, disabling the strategy")
+        };
+        let _ = self.logger.log_fields(
+            level,
+            message,
+            &[
+                ("operation", operation.into()),
+                ("strategy", strategy.into()),
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::logger::CaptureLogger;
+    use crate::pmul::PmulStrategy;
+
+    #[test]
+    fn test_disabled_strategies() {
+        let disabled = DisabledStrategies::new();
+        assert!(!disabled.is_disabled(0));
+        assert!(!disabled.is_disabled(2));
+        disabled.disable(2);
+        assert!(!disabled.is_disabled(0));
+        assert!(disabled.is_disabled(2));
+    }
+
+    #[test]
+    fn test_run_self_tests() {
+        let logger = CaptureLogger::new();
+        let failures = run_self_tests(&logger);
+        assert_eq!(failures, []);
+        assert!(!PmulStrategy::Nosimd.is_disabled());
+
+        let records = logger.with_field(Level::Debug, "strategy", "nosimd");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "Self-tests passed");
+        assert!(!logger.contains(Level::Error, "Self-test failed"));
+    }
+
+    #[test]
+    fn test_check() {
+        let logger = CaptureLogger::new();
+        let mut tests = SelfTests {
+            logger: &logger,
+            failures: Vec::new(),
+        };
+        assert!(tests.check(
+            "op",
+            "good",
+            "in".to_owned(),
+            "out".to_owned(),
+            "out".to_owned()
+        ));
+        assert!(!tests.check(
+            "op",
+            "bad",
+            "in".to_owned(),
+            "out".to_owned(),
+            "wrong".to_owned()
+        ));
+        tests.conclude("op", "bad", false);
+
+        assert_eq!(
+            tests.failures,
+            [SelfTestFailure {
+                operation: "op",
+                strategy: "bad",
+                input: "in".to_owned(),
+                expected: "out".to_owned(),
+                actual: "wrong".to_owned(),
+            }]
+        );
+        assert_eq!(
+            tests.failures[0].to_string(),
+            "op [strategy = bad]: input in, expected out, got wrong"
+        );
+
+        let records = logger.with_field(Level::Error, "strategy", "bad");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].field("output"), Some("wrong"));
+        assert!(logger.contains(Level::Warn, "disabling the strategy"));
+    }
+}
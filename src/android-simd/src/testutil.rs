@@ -0,0 +1,89 @@
+//! Helpers shared by the tests and benchmarks of several modules.
+
+use std::process::Command;
+use test::Bencher;
+
+/// Environment variable set in the child process by `run_in_subprocess()`, to the name of the test
+/// that it runs.
+const SUBPROCESS_TEST_VAR: &str = "SIMD_SUBPROCESS_TEST";
+
+/// Runs the body of a test that changes process-wide state, such as the standard file descriptors
+/// or the panic hook, isolated from the tests that run concurrently in other threads.
+///
+/// The test binary runs itself again in a child process, filtered to the given test, whose full
+/// name is its path in this crate. In the child, the body runs; in the parent, this asserts that
+/// the child ran the test and that it passed.
+pub fn run_in_subprocess(test_name: &str, body: impl FnOnce()) {
+    if std::env::var(SUBPROCESS_TEST_VAR).as_deref() == Ok(test_name) {
+        body();
+        return;
+    }
+
+    let output = Command::new(std::env::current_exe().expect("Failed to find the test binary"))
+        .args([test_name, "--exact", "--test-threads=1"])
+        .env(SUBPROCESS_TEST_VAR, test_name)
+        .output()
+        .expect("Failed to run the test binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success() && stdout.contains("test result: ok. 1 passed"),
+        "Test {test_name} failed in a subprocess ({}):\n{stdout}\n{}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Decodes a lowercase hexadecimal string of `N` bytes. It is a `const fn`, so that test vectors
+/// can be constants.
+pub const fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 2 * N, "Invalid hexadecimal length");
+    let mut result = [0; N];
+    let mut i = 0;
+    while i < N {
+        result[i] = (hex_digit(hex[2 * i]) << 4) | hex_digit(hex[2 * i + 1]);
+        i += 1;
+    }
+    result
+}
+
+/// Decodes a lowercase hexadecimal string.
+pub fn hex_bytes(hex: &str) -> Vec<u8> {
+    let hex = hex.as_bytes();
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| (hex_digit(hex[i]) << 4) | hex_digit(hex[i + 1]))
+        .collect()
+}
+
+const fn hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        _ => panic!("Invalid hexadecimal digit"),
+    }
+}
+
+/// Seed of the pseudo-random inputs, unless a test needs several independent sequences.
+pub const RANDOM_SEED: u64 = 0x243f6a8885a308d3;
+
+/// Deterministic pseudo-random words (xorshift), from a non-zero seed.
+pub fn random_u64s(count: usize, mut state: u64) -> Vec<u64> {
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect()
+}
+
+/// Runs a benchmark of a strategy if the current CPU supports it. The harness can't skip a
+/// benchmark at run time, so one that doesn't run shows as taking 0 ns/iter.
+pub fn bench_strategy(b: &mut Bencher, available: bool, routine: impl FnOnce(&mut Bencher)) {
+    if !available {
+        return;
+    }
+    routine(b);
+}
@@ -0,0 +1,797 @@
+//! AES block cipher (FIPS-197), with a portable fallback and implementations based on the AES
+//! instructions of ARMv8 and x86_64.
+//!
+//! The round keys are expanded once, both for the cipher and for the equivalent inverse cipher
+//! (FIPS-197, section 5.3.5), whose rounds map to the AESD and AESIMC instructions, or AESDEC and
+//! AESIMC on x86_64. All the implementations use the same layout, an array of `rounds + 1` keys of
+//! 16 bytes.
+//!
+//! The fallback runs in constant time, as it is used on the CPUs without the AES instructions: it
+//! evaluates the S-box with logic operations on bit planes rather than with table lookups indexed
+//! by the secret state, which would leak it through the cache timings.
+//!
+//! Counter mode increments the counter block as a 128-bit big-endian integer (NIST SP 800-38A),
+//! or only its first 32 bits as a little-endian integer for AES-GCM-SIV (RFC 8452). It encrypts
+//! several blocks at once: the instructions of independent blocks are interleaved so that their
+//! latencies overlap, and the fallback computes the S-box of all the blocks together.
+
+use std::convert::TryInto;
+
+/// Returns the number of rounds for a key of the given length, or `None` if the length isn't 16,
+/// 24 or 32 bytes.
+fn rounds(key_len: usize) -> Option<usize> {
+    match key_len {
+        16 | 24 | 32 => Some(key_len / 4 + 6),
+        _ => None,
+    }
+}
+
+/// Expands the key into the round keys of the cipher (FIPS-197, section 5.2), given the SubWord
+/// transformation on a little-endian word.
+fn expand_key(key: &[u8], round_keys: &mut [[u8; 16]], sub_word: impl Fn(u32) -> u32) {
+    let nk = key.len() / 4;
+    let mut words = [0u32; 60];
+    for (word, bytes) in words.iter_mut().zip(key.chunks(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let mut rcon = 1u8;
+    for i in nk..round_keys.len() * 4 {
+        let mut word = words[i - 1];
+        if i % nk == 0 {
+            // RotWord is a rotation by one byte, which commutes with SubWord.
+            word = sub_word(word).rotate_right(8) ^ rcon as u32;
+            rcon = (rcon << 1) ^ (((rcon >> 7) & 1) * 0x1b);
+        } else if nk > 6 && i % nk == 4 {
+            word = sub_word(word);
+        }
+        words[i] = words[i - nk] ^ word;
+    }
+
+    for (round_key, words) in round_keys.iter_mut().zip(words.chunks(4)) {
+        for (bytes, word) in round_key.chunks_mut(4).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+/// Derives the round keys of the equivalent inverse cipher: the round keys of the cipher in
+/// reverse order, with InvMixColumns applied to all but the first and last ones.
+fn invert_key_schedule(
+    enc_keys: &[[u8; 16]],
+    dec_keys: &mut [[u8; 16]],
+    inv_mix_columns: impl Fn(&mut [u8; 16]),
+) {
+    for (dec_key, enc_key) in dec_keys.iter_mut().zip(enc_keys.iter().rev()) {
+        *dec_key = *enc_key;
+    }
+    let rounds = dec_keys.len() - 1;
+    for dec_key in &mut dec_keys[1..rounds] {
+        inv_mix_columns(dec_key);
+    }
+}
+
+/// Returns the counter block `n` blocks after `counter`, incremented as a 128-bit big-endian
+/// integer that wraps around.
+fn add_ctr128_be(counter: [u8; 16], n: usize) -> [u8; 16] {
+    u128::from_be_bytes(counter)
+        .wrapping_add(n as u128)
+        .to_be_bytes()
+}
+
+/// Returns the counter block `n` blocks after `counter`, with only its first 32 bits incremented
+/// as a little-endian integer that wraps around (RFC 8452, section 4).
+fn add_ctr32_le(mut counter: [u8; 16], n: usize) -> [u8; 16] {
+    let low = u32::from_le_bytes(counter[..4].try_into().unwrap());
+    counter[..4].copy_from_slice(&low.wrapping_add(n as u32).to_le_bytes());
+    counter
+}
+
+#[cfg(not(any(
+    all(target_feature = "neon", target_feature = "aes"),
+    all(
+        target_arch = "x86_64",
+        target_feature = "aes",
+        target_feature = "pclmulqdq"
+    )
+)))]
+mod bitsliced;
+
+#[cfg(not(any(
+    all(target_feature = "neon", target_feature = "aes"),
+    all(
+        target_arch = "x86_64",
+        target_feature = "aes",
+        target_feature = "pclmulqdq"
+    )
+)))]
+mod fallback {
+    use super::bitsliced::{self, BLOCKS};
+    use super::*;
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aesenc_fallback(block: *mut u8, key: *const u8) -> u32 {
+        let state: &mut [u8; 16] = &mut *(block as *mut [u8; 16]);
+        let rkey: &[u8; 16] = &*(key as *const [u8; 16]);
+
+        subbytes(state);
+        shiftrows(state);
+        mixcolumns(state);
+        addroundkey(state, rkey);
+
+        1
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_expand_key_fallback(
+        key: *const u8,
+        key_len: usize,
+        enc_keys: *mut [u8; 16],
+        dec_keys: *mut [u8; 16],
+    ) -> u32 {
+        let rounds = match rounds(key_len) {
+            Some(rounds) => rounds,
+            None => return 0,
+        };
+        let key = std::slice::from_raw_parts(key, key_len);
+        let enc_keys = std::slice::from_raw_parts_mut(enc_keys, rounds + 1);
+        let dec_keys = std::slice::from_raw_parts_mut(dec_keys, rounds + 1);
+
+        expand_key(key, enc_keys, subword);
+        invert_key_schedule(enc_keys, dec_keys, invmixcolumns);
+
+        1
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_encrypt_fallback(
+        block: *mut u8,
+        round_keys: *const [u8; 16],
+        rounds: usize,
+    ) -> u32 {
+        let state: &mut [u8; 16] = &mut *(block as *mut [u8; 16]);
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+
+        addroundkey(state, &round_keys[0]);
+        for rkey in &round_keys[1..rounds] {
+            subbytes(state);
+            shiftrows(state);
+            mixcolumns(state);
+            addroundkey(state, rkey);
+        }
+        subbytes(state);
+        shiftrows(state);
+        addroundkey(state, &round_keys[rounds]);
+
+        1
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_decrypt_fallback(
+        block: *mut u8,
+        round_keys: *const [u8; 16],
+        rounds: usize,
+    ) -> u32 {
+        let state: &mut [u8; 16] = &mut *(block as *mut [u8; 16]);
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+
+        addroundkey(state, &round_keys[0]);
+        for rkey in &round_keys[1..rounds] {
+            invsubbytes(state);
+            invshiftrows(state);
+            invmixcolumns(state);
+            addroundkey(state, rkey);
+        }
+        invsubbytes(state);
+        invshiftrows(state);
+        addroundkey(state, &round_keys[rounds]);
+
+        1
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_ctr_fallback(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32 {
+        ctr(round_keys, rounds, counter, data, len, add_ctr128_be);
+
+        1
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_ctr32_le_fallback(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32 {
+        ctr(round_keys, rounds, counter, data, len, add_ctr32_le);
+
+        1
+    }
+
+    /// Counter mode, where `add` returns the counter block a number of blocks further.
+    unsafe fn ctr(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+        add: impl Fn([u8; 16], usize) -> [u8; 16],
+    ) {
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+        let counter: &mut [u8; 16] = &mut *(counter as *mut [u8; 16]);
+        let data = std::slice::from_raw_parts_mut(data, len);
+
+        for chunk in data.chunks_mut(16 * BLOCKS) {
+            let mut keystream = [0; 16 * BLOCKS];
+            for (i, block) in keystream.chunks_exact_mut(16).enumerate() {
+                block.copy_from_slice(&add(*counter, i));
+            }
+            encrypt_blocks(&mut keystream, round_keys);
+            for (x, k) in chunk.iter_mut().zip(keystream) {
+                *x ^= k;
+            }
+            *counter = add(*counter, (chunk.len() + 15) / 16);
+        }
+    }
+
+    /// Encrypts `BLOCKS` blocks at once, in constant time.
+    fn encrypt_blocks(blocks: &mut [u8; 16 * BLOCKS], round_keys: &[[u8; 16]]) {
+        let rounds = round_keys.len() - 1;
+        for state in blocks.chunks_exact_mut(16) {
+            addroundkey(state.try_into().unwrap(), &round_keys[0]);
+        }
+        for rkey in &round_keys[1..rounds] {
+            bitsliced::subbytes(blocks);
+            for state in blocks.chunks_exact_mut(16) {
+                let state: &mut [u8; 16] = state.try_into().unwrap();
+                shiftrows(state);
+                mixcolumns(state);
+                addroundkey(state, rkey);
+            }
+        }
+        bitsliced::subbytes(blocks);
+        for state in blocks.chunks_exact_mut(16) {
+            let state: &mut [u8; 16] = state.try_into().unwrap();
+            shiftrows(state);
+            addroundkey(state, &round_keys[rounds]);
+        }
+    }
+
+    /// Applies the S-box to a single block in constant time, at the cost of `BLOCKS` blocks.
+    fn subbytes(state: &mut [u8; 16]) {
+        let mut blocks = [0; 16 * BLOCKS];
+        blocks[..16].copy_from_slice(state);
+        bitsliced::subbytes(&mut blocks);
+        state.copy_from_slice(&blocks[..16]);
+    }
+
+    fn shiftrows(state: &mut [u8; 16]) {
+        let tmp = state[1];
+        state[1] = state[5];
+        state[5] = state[9];
+        state[9] = state[13];
+        state[13] = tmp;
+
+        let tmp = state[2];
+        state[2] = state[10];
+        state[10] = tmp;
+        let tmp = state[6];
+        state[6] = state[14];
+        state[14] = tmp;
+
+        let tmp = state[3];
+        state[3] = state[15];
+        state[15] = state[11];
+        state[11] = state[7];
+        state[7] = tmp;
+    }
+
+    // Multiplication by 2 in GF(2^8), which reduces by a mask rather than a branch on the top bit,
+    // so that MixColumns runs in constant time.
+    fn mul2(x: u8) -> u8 {
+        (x << 1) ^ (((x >> 7) & 1) * 0x1b)
+    }
+
+    fn mixcolumns(state: &mut [u8; 16]) {
+        for i in 0..4 {
+            let x0 = state[4 * i];
+            let x1 = state[4 * i + 1];
+            let x2 = state[4 * i + 2];
+            let x3 = state[4 * i + 3];
+            let x = x0 ^ x1 ^ x2 ^ x3;
+            state[4 * i] ^= mul2(x0 ^ x1) ^ x;
+            state[4 * i + 1] ^= mul2(x1 ^ x2) ^ x;
+            state[4 * i + 2] ^= mul2(x2 ^ x3) ^ x;
+            state[4 * i + 3] ^= mul2(x3 ^ x0) ^ x;
+        }
+    }
+
+    fn addroundkey(state: &mut [u8; 16], rkey: &[u8; 16]) {
+        for i in 0..16 {
+            state[i] ^= rkey[i];
+        }
+    }
+
+    fn subword(word: u32) -> u32 {
+        let mut blocks = [0; 16 * BLOCKS];
+        blocks[..4].copy_from_slice(&word.to_le_bytes());
+        bitsliced::subbytes(&mut blocks);
+        u32::from_le_bytes(blocks[..4].try_into().unwrap())
+    }
+
+    fn invsubbytes(state: &mut [u8; 16]) {
+        let mut blocks = [0; 16 * BLOCKS];
+        blocks[..16].copy_from_slice(state);
+        bitsliced::invsubbytes(&mut blocks);
+        state.copy_from_slice(&blocks[..16]);
+    }
+
+    fn invshiftrows(state: &mut [u8; 16]) {
+        let old = *state;
+        for c in 0..4 {
+            for r in 1..4 {
+                state[4 * c + r] = old[4 * ((c + 4 - r) % 4) + r];
+            }
+        }
+    }
+
+    // InvMixColumns factors as a multiplication by 4x^2 + 5 followed by MixColumns.
+    fn invmixcolumns(state: &mut [u8; 16]) {
+        for i in 0..4 {
+            let u = mul2(mul2(state[4 * i] ^ state[4 * i + 2]));
+            let v = mul2(mul2(state[4 * i + 1] ^ state[4 * i + 3]));
+            state[4 * i] ^= u;
+            state[4 * i + 1] ^= v;
+            state[4 * i + 2] ^= u;
+            state[4 * i + 3] ^= v;
+        }
+        mixcolumns(state);
+    }
+
+    /// Table-based implementations, whose memory accesses depend on the secret state and leak it
+    /// through the cache timings. They are only exported with the "table-baseline" feature, as a
+    /// baseline for the benchmarks of the constant-time implementations above.
+    #[cfg(feature = "table-baseline")]
+    mod table {
+        use super::{addroundkey, mixcolumns, shiftrows};
+
+        #[no_mangle]
+        pub unsafe extern "C" fn aesenc_fallback_table(block: *mut u8, key: *const u8) -> u32 {
+            let state: &mut [u8; 16] = &mut *(block as *mut [u8; 16]);
+            let rkey: &[u8; 16] = &*(key as *const [u8; 16]);
+
+            subbytes(state);
+            shiftrows(state);
+            mixcolumns(state);
+            addroundkey(state, rkey);
+
+            1
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn aes_encrypt_fallback_table(
+            block: *mut u8,
+            round_keys: *const [u8; 16],
+            rounds: usize,
+        ) -> u32 {
+            let state: &mut [u8; 16] = &mut *(block as *mut [u8; 16]);
+            let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+
+            addroundkey(state, &round_keys[0]);
+            for rkey in &round_keys[1..rounds] {
+                subbytes(state);
+                shiftrows(state);
+                mixcolumns(state);
+                addroundkey(state, rkey);
+            }
+            subbytes(state);
+            shiftrows(state);
+            addroundkey(state, &round_keys[rounds]);
+
+            1
+        }
+
+        fn subbytes(state: &mut [u8; 16]) {
+            for x in state.iter_mut() {
+                *x = AES_SBOX[*x as usize];
+            }
+        }
+
+        static AES_SBOX: [u8; 256] = [
+            0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7,
+            0xab, 0x76, 0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf,
+            0x9c, 0xa4, 0x72, 0xc0, 0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5,
+            0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15, 0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a,
+            0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75, 0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e,
+            0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84, 0x53, 0xd1, 0x00, 0xed,
+            0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf, 0xd0, 0xef,
+            0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+            0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff,
+            0xf3, 0xd2, 0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d,
+            0x64, 0x5d, 0x19, 0x73, 0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee,
+            0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb, 0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c,
+            0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79, 0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5,
+            0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08, 0xba, 0x78, 0x25, 0x2e,
+            0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a, 0x70, 0x3e,
+            0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+            0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55,
+            0x28, 0xdf, 0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f,
+            0xb0, 0x54, 0xbb, 0x16,
+        ];
+    }
+}
+
+#[cfg(all(target_feature = "neon", target_feature = "aes"))]
+mod simd {
+    use super::*;
+    use std::arch::aarch64::{
+        uint8x16_t, vaesdq_u8, vaeseq_u8, vaesimcq_u8, vaesmcq_u8, vdupq_n_u32, vdupq_n_u8,
+        veorq_u8, vgetq_lane_u32, vld1q_u8, vreinterpretq_u32_u8, vreinterpretq_u8_u32, vst1q_u8,
+    };
+
+    /// Number of blocks encrypted in parallel in counter mode.
+    const PARALLEL_BLOCKS: usize = 8;
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aesenc_simd(block: *mut u8, key: *const u8) -> u32 {
+        let simd_block: &mut uint8x16_t = &mut *(block as *mut uint8x16_t);
+        let simd_key: &uint8x16_t = &*(key as *const uint8x16_t);
+
+        let zero = vdupq_n_u8(0);
+        let x = vaeseq_u8(*simd_block, zero);
+        let y = vaesmcq_u8(x);
+        *simd_block = veorq_u8(y, *simd_key);
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_expand_key_simd(
+        key: *const u8,
+        key_len: usize,
+        enc_keys: *mut [u8; 16],
+        dec_keys: *mut [u8; 16],
+    ) -> u32 {
+        let rounds = match rounds(key_len) {
+            Some(rounds) => rounds,
+            None => return 0,
+        };
+        let key = std::slice::from_raw_parts(key, key_len);
+        let enc_keys = std::slice::from_raw_parts_mut(enc_keys, rounds + 1);
+        let dec_keys = std::slice::from_raw_parts_mut(dec_keys, rounds + 1);
+
+        expand_key(key, enc_keys, subword);
+        invert_key_schedule(enc_keys, dec_keys, |rkey| {
+            vst1q_u8(rkey.as_mut_ptr(), vaesimcq_u8(vld1q_u8(rkey.as_ptr())))
+        });
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_encrypt_simd(
+        block: *mut u8,
+        round_keys: *const [u8; 16],
+        rounds: usize,
+    ) -> u32 {
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+
+        // AESE adds the round key before SubBytes and ShiftRows, so the last key is added apart.
+        let mut state = vld1q_u8(block);
+        for rkey in &round_keys[..rounds - 1] {
+            state = vaesmcq_u8(vaeseq_u8(state, vld1q_u8(rkey.as_ptr())));
+        }
+        state = vaeseq_u8(state, vld1q_u8(round_keys[rounds - 1].as_ptr()));
+        state = veorq_u8(state, vld1q_u8(round_keys[rounds].as_ptr()));
+        vst1q_u8(block, state);
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_decrypt_simd(
+        block: *mut u8,
+        round_keys: *const [u8; 16],
+        rounds: usize,
+    ) -> u32 {
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+
+        let mut state = vld1q_u8(block);
+        for rkey in &round_keys[..rounds - 1] {
+            state = vaesimcq_u8(vaesdq_u8(state, vld1q_u8(rkey.as_ptr())));
+        }
+        state = vaesdq_u8(state, vld1q_u8(round_keys[rounds - 1].as_ptr()));
+        state = veorq_u8(state, vld1q_u8(round_keys[rounds].as_ptr()));
+        vst1q_u8(block, state);
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_ctr_simd(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32 {
+        ctr(round_keys, rounds, counter, data, len, add_ctr128_be);
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_ctr32_le_simd(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32 {
+        ctr(round_keys, rounds, counter, data, len, add_ctr32_le);
+
+        2
+    }
+
+    /// Counter mode, where `add` returns the counter block a number of blocks further.
+    unsafe fn ctr(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+        add: impl Fn([u8; 16], usize) -> [u8; 16] + Copy,
+    ) {
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+        let counter: &mut [u8; 16] = &mut *(counter as *mut [u8; 16]);
+        let data = std::slice::from_raw_parts_mut(data, len);
+
+        let mut chunks = data.chunks_exact_mut(16 * PARALLEL_BLOCKS);
+        for chunk in &mut chunks {
+            apply_keystream(chunk, round_keys, *counter, add);
+            *counter = add(*counter, PARALLEL_BLOCKS);
+        }
+        let remainder = chunks.into_remainder();
+        if !remainder.is_empty() {
+            let mut buffer = [0; 16 * PARALLEL_BLOCKS];
+            buffer[..remainder.len()].copy_from_slice(remainder);
+            apply_keystream(&mut buffer, round_keys, *counter, add);
+            remainder.copy_from_slice(&buffer[..remainder.len()]);
+            *counter = add(*counter, (remainder.len() + 15) / 16);
+        }
+    }
+
+    /// XORs `PARALLEL_BLOCKS` blocks of data with the keystream from the given counter.
+    unsafe fn apply_keystream(
+        data: &mut [u8],
+        round_keys: &[[u8; 16]],
+        counter: [u8; 16],
+        add: impl Fn([u8; 16], usize) -> [u8; 16],
+    ) {
+        let rounds = round_keys.len() - 1;
+        let mut blocks = [vdupq_n_u8(0); PARALLEL_BLOCKS];
+        for (i, block) in blocks.iter_mut().enumerate() {
+            *block = vld1q_u8(add(counter, i).as_ptr());
+        }
+        // Each round is applied to all the blocks before the next one, so that the latencies of
+        // the independent AESE and AESMC instructions overlap.
+        for rkey in &round_keys[..rounds - 1] {
+            let rkey = vld1q_u8(rkey.as_ptr());
+            for block in blocks.iter_mut() {
+                *block = vaesmcq_u8(vaeseq_u8(*block, rkey));
+            }
+        }
+        let penultimate = vld1q_u8(round_keys[rounds - 1].as_ptr());
+        let last = vld1q_u8(round_keys[rounds].as_ptr());
+        for (block, data) in blocks.iter().zip(data.chunks_exact_mut(16)) {
+            let keystream = veorq_u8(vaeseq_u8(*block, penultimate), last);
+            vst1q_u8(
+                data.as_mut_ptr(),
+                veorq_u8(vld1q_u8(data.as_ptr()), keystream),
+            );
+        }
+    }
+
+    fn subword(word: u32) -> u32 {
+        // With the same word in all the columns, ShiftRows is the identity, so AESE with a zero
+        // key only applies SubBytes.
+        unsafe {
+            let state = vreinterpretq_u8_u32(vdupq_n_u32(word));
+            let state = vaeseq_u8(state, vdupq_n_u8(0));
+            vgetq_lane_u32(vreinterpretq_u32_u8(state), 0)
+        }
+    }
+}
+
+#[cfg(all(
+    target_arch = "x86_64",
+    target_feature = "aes",
+    target_feature = "pclmulqdq"
+))]
+mod aesni {
+    use super::*;
+    use std::arch::x86_64::{
+        __m128i, _mm_aesdec_si128, _mm_aesdeclast_si128, _mm_aesenc_si128, _mm_aesenclast_si128,
+        _mm_aesimc_si128, _mm_cvtsi128_si32, _mm_loadu_si128, _mm_set1_epi32, _mm_setzero_si128,
+        _mm_storeu_si128, _mm_xor_si128,
+    };
+
+    /// Number of blocks encrypted in parallel in counter mode.
+    const PARALLEL_BLOCKS: usize = 8;
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aesenc_simd(block: *mut u8, key: *const u8) -> u32 {
+        // AESENC applies SubBytes, ShiftRows, MixColumns and AddRoundKey, like the fallback.
+        store(block, _mm_aesenc_si128(load(block), load(key)));
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_expand_key_simd(
+        key: *const u8,
+        key_len: usize,
+        enc_keys: *mut [u8; 16],
+        dec_keys: *mut [u8; 16],
+    ) -> u32 {
+        let rounds = match rounds(key_len) {
+            Some(rounds) => rounds,
+            None => return 0,
+        };
+        let key = std::slice::from_raw_parts(key, key_len);
+        let enc_keys = std::slice::from_raw_parts_mut(enc_keys, rounds + 1);
+        let dec_keys = std::slice::from_raw_parts_mut(dec_keys, rounds + 1);
+
+        expand_key(key, enc_keys, subword);
+        invert_key_schedule(enc_keys, dec_keys, |rkey| {
+            store(rkey.as_mut_ptr(), _mm_aesimc_si128(load(rkey.as_ptr())))
+        });
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_encrypt_simd(
+        block: *mut u8,
+        round_keys: *const [u8; 16],
+        rounds: usize,
+    ) -> u32 {
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+
+        let mut state = _mm_xor_si128(load(block), load(round_keys[0].as_ptr()));
+        for rkey in &round_keys[1..rounds] {
+            state = _mm_aesenc_si128(state, load(rkey.as_ptr()));
+        }
+        state = _mm_aesenclast_si128(state, load(round_keys[rounds].as_ptr()));
+        store(block, state);
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_decrypt_simd(
+        block: *mut u8,
+        round_keys: *const [u8; 16],
+        rounds: usize,
+    ) -> u32 {
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+
+        let mut state = _mm_xor_si128(load(block), load(round_keys[0].as_ptr()));
+        for rkey in &round_keys[1..rounds] {
+            state = _mm_aesdec_si128(state, load(rkey.as_ptr()));
+        }
+        state = _mm_aesdeclast_si128(state, load(round_keys[rounds].as_ptr()));
+        store(block, state);
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_ctr_simd(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32 {
+        ctr(round_keys, rounds, counter, data, len, add_ctr128_be);
+
+        2
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn aes_ctr32_le_simd(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+    ) -> u32 {
+        ctr(round_keys, rounds, counter, data, len, add_ctr32_le);
+
+        2
+    }
+
+    /// Counter mode, where `add` returns the counter block a number of blocks further.
+    unsafe fn ctr(
+        round_keys: *const [u8; 16],
+        rounds: usize,
+        counter: *mut u8,
+        data: *mut u8,
+        len: usize,
+        add: impl Fn([u8; 16], usize) -> [u8; 16] + Copy,
+    ) {
+        let round_keys = std::slice::from_raw_parts(round_keys, rounds + 1);
+        let counter: &mut [u8; 16] = &mut *(counter as *mut [u8; 16]);
+        let data = std::slice::from_raw_parts_mut(data, len);
+
+        let mut chunks = data.chunks_exact_mut(16 * PARALLEL_BLOCKS);
+        for chunk in &mut chunks {
+            apply_keystream(chunk, round_keys, *counter, add);
+            *counter = add(*counter, PARALLEL_BLOCKS);
+        }
+        let remainder = chunks.into_remainder();
+        if !remainder.is_empty() {
+            let mut buffer = [0; 16 * PARALLEL_BLOCKS];
+            buffer[..remainder.len()].copy_from_slice(remainder);
+            apply_keystream(&mut buffer, round_keys, *counter, add);
+            remainder.copy_from_slice(&buffer[..remainder.len()]);
+            *counter = add(*counter, (remainder.len() + 15) / 16);
+        }
+    }
+
+    /// XORs `PARALLEL_BLOCKS` blocks of data with the keystream from the given counter.
+    unsafe fn apply_keystream(
+        data: &mut [u8],
+        round_keys: &[[u8; 16]],
+        counter: [u8; 16],
+        add: impl Fn([u8; 16], usize) -> [u8; 16],
+    ) {
+        let rounds = round_keys.len() - 1;
+        let first = load(round_keys[0].as_ptr());
+        let mut blocks = [_mm_setzero_si128(); PARALLEL_BLOCKS];
+        for (i, block) in blocks.iter_mut().enumerate() {
+            *block = _mm_xor_si128(load(add(counter, i).as_ptr()), first);
+        }
+        // Each round is applied to all the blocks before the next one, so that the latencies of
+        // the independent AESENC instructions overlap.
+        for rkey in &round_keys[1..rounds] {
+            let rkey = load(rkey.as_ptr());
+            for block in blocks.iter_mut() {
+                *block = _mm_aesenc_si128(*block, rkey);
+            }
+        }
+        let last = load(round_keys[rounds].as_ptr());
+        for (block, data) in blocks.iter().zip(data.chunks_exact_mut(16)) {
+            let keystream = _mm_aesenclast_si128(*block, last);
+            store(
+                data.as_mut_ptr(),
+                _mm_xor_si128(load(data.as_ptr()), keystream),
+            );
+        }
+    }
+
+    unsafe fn load(bytes: *const u8) -> __m128i {
+        _mm_loadu_si128(bytes as *const __m128i)
+    }
+
+    unsafe fn store(bytes: *mut u8, x: __m128i) {
+        _mm_storeu_si128(bytes as *mut __m128i, x)
+    }
+
+    fn subword(word: u32) -> u32 {
+        // With the same word in all the columns, ShiftRows is the identity, so AESENCLAST with a
+        // zero key only applies SubBytes.
+        unsafe {
+            let state = _mm_aesenclast_si128(_mm_set1_epi32(word as i32), _mm_setzero_si128());
+            _mm_cvtsi128_si32(state) as u32
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Constant-time SubBytes and InvSubBytes on several blocks at once, for the fallback
+//! implementation.
+//!
+//! The bytes are transposed into bit planes: each of the 8 words holds one bit of each of the 64
+//! bytes of 4 blocks. The S-box is then evaluated with logic operations only, as the affine
+//! transformation of the inverse x^254 in GF(2^8), without any memory access that depends on the
+//! state. The inverse S-box applies the inverse affine transformation first, as x^254 is an
+//! involution. Processing fewer blocks costs as much, the unused bytes being computed anyway.
+
+/// Number of blocks processed at once.
+pub const BLOCKS: usize = 4;
+
+/// Bit planes of `BLOCKS` blocks: bit j of plane b is bit b of byte j.
+type Planes = [u64; 8];
+
+/// Transposes the 8x8 bit matrix whose rows are the bytes of x: bit j of byte i becomes bit i of
+/// byte j.
+fn transpose8x8(x: u64) -> u64 {
+    let t = (x ^ (x >> 7)) & 0x00aa00aa00aa00aa;
+    let x = x ^ t ^ (t << 7);
+    let t = (x ^ (x >> 14)) & 0x0000cccc0000cccc;
+    let x = x ^ t ^ (t << 14);
+    let t = (x ^ (x >> 28)) & 0x00000000f0f0f0f0;
+    x ^ t ^ (t << 28)
+}
+
+fn to_planes(bytes: &[u8; 16 * BLOCKS]) -> Planes {
+    let mut planes = [0; 8];
+    for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+        let t = transpose8x8(u64::from_le_bytes(chunk.try_into().unwrap()));
+        for (b, plane) in planes.iter_mut().enumerate() {
+            *plane |= ((t >> (8 * b)) & 0xff) << (8 * i);
+        }
+    }
+    planes
+}
+
+fn from_planes(planes: &Planes, bytes: &mut [u8; 16 * BLOCKS]) {
+    for (i, chunk) in bytes.chunks_exact_mut(8).enumerate() {
+        let mut t = 0;
+        for (b, plane) in planes.iter().enumerate() {
+            t |= ((plane >> (8 * i)) & 0xff) << (8 * b);
+        }
+        chunk.copy_from_slice(&transpose8x8(t).to_le_bytes());
+    }
+}
+
+/// Reduces a product of polynomials of degree 7 modulo x^8 + x^4 + x^3 + x + 1.
+fn reduce(mut p: [u64; 15]) -> Planes {
+    for k in (8..15).rev() {
+        p[k - 4] ^= p[k];
+        p[k - 5] ^= p[k];
+        p[k - 7] ^= p[k];
+        p[k - 8] ^= p[k];
+    }
+    p[..8].try_into().unwrap()
+}
+
+fn mul(a: &Planes, b: &Planes) -> Planes {
+    let mut p = [0; 15];
+    for i in 0..8 {
+        for j in 0..8 {
+            p[i + j] ^= a[i] & b[j];
+        }
+    }
+    reduce(p)
+}
+
+/// Squares the elements, which is linear in GF(2^8).
+fn square(a: &Planes) -> Planes {
+    let mut p = [0; 15];
+    for i in 0..8 {
+        p[2 * i] = a[i];
+    }
+    reduce(p)
+}
+
+/// Computes x^254, the inverse of x for non-zero elements, which maps zero to itself.
+fn invert(x: &Planes) -> Planes {
+    let x2 = square(x);
+    let x3 = mul(&x2, x);
+    let x12 = square(&square(&x3));
+    let x15 = mul(&x12, &x3);
+    let x240 = square(&square(&square(&square(&x15))));
+    let x252 = mul(&x240, &x12);
+    mul(&x252, &x2)
+}
+
+/// Applies an affine transformation over GF(2): bit i of the output is the XOR of the bits i + r
+/// (mod 8) of the input for each r in `rotations`, and of bit i of `constant`.
+fn affine(x: &Planes, rotations: &[usize], constant: u8) -> Planes {
+    let mut y = [0; 8];
+    for (i, y) in y.iter_mut().enumerate() {
+        for r in rotations {
+            *y ^= x[(i + r) % 8];
+        }
+        // The constant is public, so branching on it is fine.
+        if (constant >> i) & 1 != 0 {
+            *y = !*y;
+        }
+    }
+    y
+}
+
+/// Applies the S-box to all the bytes of the blocks.
+pub fn subbytes(state: &mut [u8; 16 * BLOCKS]) {
+    let x = invert(&to_planes(state));
+    from_planes(&affine(&x, &[0, 4, 5, 6, 7], 0x63), state);
+}
+
+/// Applies the inverse S-box to all the bytes of the blocks.
+pub fn invsubbytes(state: &mut [u8; 16 * BLOCKS]) {
+    let x = affine(&to_planes(state), &[2, 5, 7], 0x05);
+    from_planes(&invert(&x), state);
+}